@@ -0,0 +1,154 @@
+//! Module defining the `Autosave` observer, which saves a `CourseList` as it changes, according
+//! to an `AutosavePolicy`.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::courses::course_list::CourseList;
+use crate::courses::observer::{Change, Observer};
+
+/// When to save a list automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutosavePolicy {
+    /// Save after every change.
+    EveryChange,
+    /// Save after every N changes, or once the list has gone `IDLE_SAVE` without changes.
+    EveryActions(usize),
+    /// Save on a change, unless the list was saved less than this long ago. Changes made within
+    /// the interval are saved once it has passed.
+    Interval(Duration),
+}
+
+impl FromStr for AutosavePolicy {
+    type Err = String;
+
+    /// Parse a policy: "change" for `EveryChange`, a number of changes like "5" for
+    /// `EveryActions`, or a number of seconds like "30s" for `Interval`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        if s == "change" {
+            return Ok(AutosavePolicy::EveryChange);
+        }
+
+        if let Some(secs) = s.strip_suffix('s') {
+            let secs: u64 = secs
+                .parse()
+                .map_err(|_| format!("Invalid number of seconds '{secs}'"))?;
+            return Ok(AutosavePolicy::Interval(Duration::from_secs(secs)));
+        }
+
+        match s.parse() {
+            Ok(0) | Err(_) => Err(format!(
+                "Invalid autosave policy '{s}': expected 'change', a number of changes, or a \
+                number of seconds like '30s'"
+            )),
+            Ok(n) => Ok(AutosavePolicy::EveryActions(n)),
+        }
+    }
+}
+
+impl fmt::Display for AutosavePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AutosavePolicy::EveryChange => write!(f, "after every change"),
+            AutosavePolicy::EveryActions(n) => write!(f, "every {n} changes"),
+            AutosavePolicy::Interval(t) => write!(f, "at most every {}s", t.as_secs()),
+        }
+    }
+}
+
+/// Saves a list with `CourseList::dump_list` as it changes. Register it with
+/// `CourseList::subscribe`. Nothing is saved until the first change after registering.
+///
+/// Changes the policy holds back are saved once they are due, by `CourseList::flush_observers`:
+/// with `Interval`, once the interval has passed, and with `EveryActions`, once the list has
+/// gone `IDLE_SAVE` without changes. Closing the list saves whatever is left.
+///
+/// If the save file was modified by something else since it was loaded, autosaving is held back
+/// until the list is saved by hand. See `CourseList::modified_externally`. Read only lists are
+/// never saved. Failed saves are reported as observer errors. See
+/// `CourseList::take_observer_errors`.
+#[derive(Debug, Clone)]
+pub struct Autosave {
+    policy: AutosavePolicy,
+    pending: usize,
+    last_save: Instant,
+    // When the pending changes are due to be saved, if they are not saved on the next change
+    due: Option<Instant>,
+}
+
+/// How long a list autosaved `EveryActions` can go without changes before the changes made so
+/// far are saved anyway.
+pub const IDLE_SAVE: Duration = Duration::from_mins(1);
+
+impl Autosave {
+    /// Create an autosaver following the given policy.
+    pub fn new(policy: AutosavePolicy) -> Self {
+        Autosave {
+            policy,
+            pending: 0,
+            last_save: Instant::now(),
+            due: None,
+        }
+    }
+
+    fn save(&mut self, list: &CourseList) -> io::Result<()> {
+        self.due = None;
+        // Saving by hand saves the pending changes too
+        if !list.is_dirty() {
+            self.pending = 0;
+            return Ok(());
+        }
+
+        if list.modified_externally()? {
+            return Err(io::Error::other(
+                "not autosaving: the save file was modified since it was loaded",
+            ));
+        }
+
+        list.dump_list()
+            .map_err(|e| io::Error::new(e.kind(), format!("autosaving: {e}")))?;
+        self.pending = 0;
+        self.last_save = Instant::now();
+        Ok(())
+    }
+}
+
+impl Observer for Autosave {
+    fn on_change(&mut self, change: &Change) -> io::Result<()> {
+        if change.after.is_read_only() {
+            return Ok(());
+        }
+
+        self.pending += 1;
+        let due = match self.policy {
+            AutosavePolicy::EveryChange => true,
+            AutosavePolicy::EveryActions(n) => self.pending >= n,
+            AutosavePolicy::Interval(t) => self.last_save.elapsed() >= t,
+        };
+        if due {
+            return self.save(change.after);
+        }
+
+        self.due = match self.policy {
+            AutosavePolicy::EveryChange => None,
+            AutosavePolicy::EveryActions(_) => Some(Instant::now() + IDLE_SAVE),
+            AutosavePolicy::Interval(t) => Some(self.last_save + t),
+        };
+        Ok(())
+    }
+
+    fn on_flush(&mut self, list: &CourseList, closing: bool) -> io::Result<()> {
+        let due = self.due.is_some_and(|due| due <= Instant::now());
+        if list.is_read_only() || self.pending == 0 || !(closing || due) {
+            return Ok(());
+        }
+        self.save(list)
+    }
+
+    fn flush_due(&self) -> Option<Instant> {
+        self.due
+    }
+}
@@ -0,0 +1,74 @@
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+
+use anyhow::{self, Context};
+use serde_json::{Value, json};
+use tungstenite::{Message, WebSocket};
+
+use mk8d_random::courses::event::Event;
+use mk8d_random::courses::observer::{Change, Observer};
+
+use crate::server::state_json;
+
+// Every message is a JSON object with the event that happened ("event", null when replaying on
+// connect) and the full state after it ("state")
+pub struct Broadcaster {
+    clients: Arc<Mutex<Vec<WebSocket<TcpStream>>>>,
+    state: Arc<Mutex<Value>>,
+}
+
+impl Broadcaster {
+    pub fn bind(port: u16, state: Value) -> anyhow::Result<Self> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .context(format!("Binding WebSocket port {port}"))?;
+        println!("Streaming events on ws://127.0.0.1:{port}.");
+
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let state = Arc::new(Mutex::new(state));
+
+        let accepted = Arc::clone(&clients);
+        let current = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else { continue };
+                let Ok(mut client) = tungstenite::accept(stream) else {
+                    continue;
+                };
+
+                let state = current
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .clone();
+                if send(&mut client, None, &state) {
+                    accepted
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .push(client);
+                }
+            }
+        });
+
+        Ok(Self { clients, state })
+    }
+}
+
+impl Observer for Broadcaster {
+    fn on_change(&mut self, change: &Change) -> io::Result<()> {
+        let state = state_json(change.after);
+        self.clients
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain_mut(|client| send(client, Some(change.event), &state));
+
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = state;
+        Ok(())
+    }
+}
+
+// Returns whether the message was sent. Clients that fail are dropped by the caller
+fn send(client: &mut WebSocket<TcpStream>, event: Option<&Event>, state: &Value) -> bool {
+    let message = json!({ "event": event, "state": state });
+    client.send(Message::text(message.to_string())).is_ok()
+}
@@ -0,0 +1,235 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{self, Context, bail};
+use clap::Subcommand;
+
+use mk8d_random::courses::course::Course;
+use mk8d_random::courses::course_list::CourseList;
+use mk8d_random::courses::game::{self, GameDefinition};
+use mk8d_random::courses::interchange::{self, CourseFormat};
+use mk8d_random::report::{self, ReportFormat};
+use mk8d_random::saves;
+
+use crate::new_list;
+
+// Commands run without starting a session
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    #[command(subcommand, about = "Manage saves")]
+    Saves(SaveCommand),
+
+    #[command(subcommand, about = "Import and export course data as CSV or TOML")]
+    Courses(CourseCommand),
+
+    #[command(
+        about = "Write a report of a save's history and results, e.g. 'save.json recap.md' for \
+            Markdown or 'save.json recap.html' for HTML"
+    )]
+    Export { save: PathBuf, file: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SaveCommand {
+    #[command(about = "List saves")]
+    List,
+
+    #[command(about = "Rename a save, e.g. 'old.json new.json'")]
+    Rename { from: PathBuf, to: String },
+
+    #[command(about = "Copy a save, e.g. 'old.json new.json'")]
+    Copy { from: PathBuf, to: String },
+
+    #[command(about = "Move a save to the trash")]
+    Delete { name: PathBuf },
+
+    #[command(about = "Create a save with every course active and no history")]
+    New {
+        name: String,
+
+        #[arg(
+            short,
+            help = "Save to take the game and courses from, e.g. 'save.json'. Defaults to mk8d"
+        )]
+        template: Option<PathBuf>,
+
+        #[arg(
+            short,
+            conflicts_with = "template",
+            help = "Game to take the courses from, e.g. 'mk8d'"
+        )]
+        game: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CourseCommand {
+    #[command(
+        about = "Write the courses of a save to a file, e.g. 'courses.csv' or 'courses.toml'"
+    )]
+    Export {
+        file: PathBuf,
+
+        #[arg(
+            short,
+            help = "Save to take the courses from, e.g. 'save.json'. Defaults to mk8d"
+        )]
+        save: Option<PathBuf>,
+    },
+
+    #[command(about = "Create a save from the courses in a file, e.g. 'courses.csv new.json'")]
+    Import { file: PathBuf, name: String },
+
+    #[command(
+        about = "Create or update a game from the courses in a file, e.g. 'courses.csv my_game'. \
+            Saves linked to the game are updated when next loaded"
+    )]
+    Define { file: PathBuf, id: String },
+}
+
+pub fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Saves(command) => run_saves(command),
+        Command::Courses(command) => run_courses(command),
+        Command::Export { save, file } => {
+            let list = CourseList::restore_save_read_only(save).context("Loading the save")?;
+            let name = list.save_name.clone();
+            write_report(&list, &file)?;
+            println!(
+                "Wrote a report of {} to {}.",
+                name.display(),
+                file.display()
+            );
+            Ok(())
+        }
+    }
+}
+
+// Write a report of `list` to `file`, in the format matching its extension
+pub fn write_report(list: &CourseList, file: &Path) -> anyhow::Result<()> {
+    let Some(format) = ReportFormat::from_path(file) else {
+        bail!(
+            "Unknown format for {}: expected a .md or .html file",
+            file.display()
+        );
+    };
+    fs::write(file, report::write_report(list, format))
+        .context(format!("Writing {}", file.display()))
+}
+
+fn run_saves(command: SaveCommand) -> anyhow::Result<()> {
+    match command {
+        SaveCommand::List => {
+            for save in saves::list_saves().context("Reading saves")? {
+                println!("{save}");
+            }
+        }
+
+        SaveCommand::Rename { from, to } => {
+            let to = saves::rename_save(&from, &to).context("Renaming save")?;
+            println!("Renamed {} to {}.", from.display(), to.display());
+        }
+
+        SaveCommand::Copy { from, to } => {
+            let to = saves::copy_save(&from, &to).context("Copying save")?;
+            println!("Copied {} to {}.", from.display(), to.display());
+        }
+
+        SaveCommand::Delete { name } => {
+            saves::delete_save(&name).context("Deleting save")?;
+        }
+
+        SaveCommand::New {
+            name,
+            template,
+            game,
+        } => {
+            let template = match game {
+                Some(game) => new_list(&game)?,
+                None => load_or_default(template).context("Loading the template save")?,
+            };
+            let name = saves::create_save(&name, &template).context("Creating save")?;
+            println!("Created {}.", name.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn run_courses(command: CourseCommand) -> anyhow::Result<()> {
+    match command {
+        CourseCommand::Export { file, save } => {
+            let format = course_format(&file)?;
+            let list = load_or_default(save).context("Loading the save")?;
+            let data =
+                interchange::export_courses(&list.courses, format).context("Exporting courses")?;
+            fs::write(&file, data).context(format!("Writing {}", file.display()))?;
+            println!(
+                "Exported {} courses to {}.",
+                list.courses.len(),
+                file.display()
+            );
+        }
+
+        CourseCommand::Import { file, name } => {
+            let courses = import_file(&file)?;
+            let count = courses.len();
+            let template = CourseList::from_courses(PathBuf::new(), courses);
+            let name = saves::create_save(&name, &template).context("Creating save")?;
+            println!("Created {} with {count} courses.", name.display());
+        }
+
+        CourseCommand::Define { file, id } => {
+            let courses = import_file(&file)?;
+            let version = match GameDefinition::load(&id) {
+                Ok(game) => game.version + 1,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => 1,
+                Err(e) => return Err(e).context("Loading the current game"),
+            };
+
+            let game = GameDefinition {
+                id,
+                version,
+                courses,
+            };
+            let path = game.save().context("Saving the game")?;
+            println!("Wrote {} to {}.", game.game_ref(), path.display());
+        }
+    }
+
+    Ok(())
+}
+
+fn import_file(file: &Path) -> anyhow::Result<Vec<Course>> {
+    let format = course_format(file)?;
+    let data = fs::read_to_string(file).context(format!("Reading {}", file.display()))?;
+
+    match interchange::import_courses(&data, format) {
+        Ok(courses) => Ok(courses),
+        Err(errors) => {
+            for e in &errors {
+                eprintln!("{}: {e}", file.display());
+            }
+            bail!("Invalid course data in {}", file.display());
+        }
+    }
+}
+
+// Load a save read only, or the default save if none is given
+fn load_or_default(save: Option<PathBuf>) -> anyhow::Result<CourseList> {
+    Ok(match save {
+        Some(save) => CourseList::restore_save_read_only(save)?,
+        None => new_list(game::BUILTIN_ID)?,
+    })
+}
+
+fn course_format(file: &Path) -> anyhow::Result<CourseFormat> {
+    match CourseFormat::from_path(file) {
+        Some(format) => Ok(format),
+        None => bail!(
+            "Unknown format for {}: expected a .csv or .toml file",
+            file.display()
+        ),
+    }
+}
@@ -0,0 +1,236 @@
+//! Module for combining several players' course rankings into one shared ranking.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::course::CourseId;
+
+/// Method used to combine several rankings into one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AggregationMethod {
+    /// Each ranking awards N - 1 points to its first course, N - 2 to its second, and so on. A
+    /// course missing from a ranking shares the remaining points equally with the other missing
+    /// courses. Courses are ordered by total points.
+    Borda,
+    /// Approximate Kemeny-Young: start from the Borda order, then repeatedly swap neighbouring
+    /// courses whenever more rankings prefer the lower one, until no swap helps. The result
+    /// cannot be improved by any single swap of neighbours.
+    Kemeny,
+    /// Courses are ordered by their average position among the rankings that include them.
+    /// Missing courses do not count against a course, unlike `Borda`.
+    AverageRank,
+}
+
+/// How much the given rankings disagree about a course.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contested {
+    /// Id of the course.
+    pub course: CourseId,
+    /// Best rank any ranking gave the course, starting at 1.
+    pub best: usize,
+    /// Worst rank any ranking gave the course, starting at 1.
+    pub worst: usize,
+    /// Standard deviation of the ranks given to the course.
+    pub std_dev: f64,
+}
+
+/// Combine `rankings`, each a list of course ids, best first, into one full ranking of
+/// `courses`, best first. Rankings may be partial, and courses they rank that are not in
+/// `courses` are ignored. Ties are broken by the order of `courses`.
+pub fn aggregate(
+    rankings: &[&[CourseId]],
+    courses: &[CourseId],
+    method: AggregationMethod,
+) -> Vec<CourseId> {
+    let num_courses = courses.len();
+    let dense = to_dense(rankings, courses);
+    let rankings: Vec<&[usize]> = dense.iter().map(Vec::as_slice).collect();
+    let rankings = rankings.as_slice();
+    let mut order: Vec<usize> = (0..num_courses).collect();
+
+    match method {
+        AggregationMethod::Borda | AggregationMethod::Kemeny => {
+            let positions = positions(rankings, num_courses);
+            let totals: Vec<f64> = (0..num_courses)
+                .map(|c| positions.iter().map(|p| p[c]).sum())
+                .collect();
+            order.sort_by(|&a, &b| totals[a].total_cmp(&totals[b]));
+
+            if method == AggregationMethod::Kemeny {
+                kemenize(&mut order, &positions);
+            }
+        }
+
+        AggregationMethod::AverageRank => {
+            let mut sums = vec![0.0; num_courses];
+            let mut counts = vec![0_u32; num_courses];
+            for ranking in rankings {
+                for (pos, &c) in ranking.iter().enumerate() {
+                    sums[c] += to_f64(pos);
+                    counts[c] += 1;
+                }
+            }
+
+            let average = |c: usize| (counts[c] > 0).then(|| sums[c] / f64::from(counts[c]));
+            order.sort_by(|&a, &b| match (average(a), average(b)) {
+                (Some(x), Some(y)) => x.total_cmp(&y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+    }
+
+    order.into_iter().map(|c| courses[c]).collect()
+}
+
+/// Rate how much `rankings` disagree about each course they include, most contested first.
+pub fn contested(rankings: &[&[CourseId]]) -> Vec<Contested> {
+    let mut ranks: BTreeMap<CourseId, Vec<usize>> = BTreeMap::new();
+    for ranking in rankings {
+        for (pos, &c) in ranking.iter().enumerate() {
+            ranks.entry(c).or_default().push(pos + 1);
+        }
+    }
+
+    let mut res: Vec<Contested> = ranks
+        .into_iter()
+        .map(|(course, r)| {
+            let len = to_f64(r.len());
+            let mean = r.iter().map(|&x| to_f64(x)).sum::<f64>() / len;
+            let variance = r.iter().map(|&x| (to_f64(x) - mean).powi(2)).sum::<f64>() / len;
+
+            Contested {
+                course,
+                best: r.iter().copied().min().unwrap_or_default(),
+                worst: r.iter().copied().max().unwrap_or_default(),
+                std_dev: variance.sqrt(),
+            }
+        })
+        .collect();
+
+    res.sort_by(|a, b| b.std_dev.total_cmp(&a.std_dev));
+    res
+}
+
+// Rankings with each course replaced by its position in `courses`
+fn to_dense(rankings: &[&[CourseId]], courses: &[CourseId]) -> Vec<Vec<usize>> {
+    let positions: BTreeMap<CourseId, usize> =
+        courses.iter().enumerate().map(|(i, &c)| (c, i)).collect();
+    rankings
+        .iter()
+        .map(|ranking| {
+            ranking
+                .iter()
+                .filter_map(|c| positions.get(c).copied())
+                .collect()
+        })
+        .collect()
+}
+
+// Zero-based position of every course in every ranking. Courses missing from a ranking share the
+// positions after its end, so they all get the average of those positions.
+fn positions(rankings: &[&[usize]], num_courses: usize) -> Vec<Vec<f64>> {
+    rankings
+        .iter()
+        .map(|ranking| {
+            let missing = to_f64(ranking.len() + num_courses.saturating_sub(1)) / 2.0;
+            let mut res = vec![missing; num_courses];
+            for (pos, &c) in ranking.iter().enumerate() {
+                res[c] = to_f64(pos);
+            }
+            res
+        })
+        .collect()
+}
+
+fn kemenize(order: &mut [usize], positions: &[Vec<f64>]) {
+    let prefers = |a: usize, b: usize| positions.iter().filter(|p| p[a] < p[b]).count();
+
+    let mut swapped = true;
+    while swapped {
+        swapped = false;
+        for i in 1..order.len() {
+            let (upper, lower) = (order[i - 1], order[i]);
+            if prefers(lower, upper) > prefers(upper, lower) {
+                order.swap(i - 1, i);
+                swapped = true;
+            }
+        }
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn to_f64(x: usize) -> f64 {
+    x as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[u32]) -> Vec<CourseId> {
+        ids.iter().copied().map(CourseId).collect()
+    }
+
+    fn combine(rankings: &[&[u32]], courses: &[u32], method: AggregationMethod) -> Vec<u32> {
+        let rankings: Vec<Vec<CourseId>> = rankings.iter().map(|r| ids(r)).collect();
+        let rankings: Vec<&[CourseId]> = rankings.iter().map(Vec::as_slice).collect();
+        aggregate(&rankings, &ids(courses), method)
+            .into_iter()
+            .map(|c| c.0)
+            .collect()
+    }
+
+    #[test]
+    fn borda_and_kemeny_can_disagree() {
+        // Two players put 1 just above 0, one puts 1 last: Borda favours 0 on points, but most
+        // players prefer 1
+        let rankings: &[&[u32]] = &[&[1, 0, 2, 3], &[1, 0, 2, 3], &[0, 2, 3, 1]];
+        let courses = [0, 1, 2, 3];
+        assert_eq!(
+            combine(rankings, &courses, AggregationMethod::Borda),
+            [0, 1, 2, 3]
+        );
+        assert_eq!(
+            combine(rankings, &courses, AggregationMethod::Kemeny),
+            [1, 0, 2, 3]
+        );
+    }
+
+    #[test]
+    fn partial_rankings() {
+        // Borda shares the leftover points between missing courses, so 0 and 1 get 1.5 each from
+        // the first ranking. Average rank ignores them, leaving 1 and 2 tied on 1
+        let rankings: &[&[u32]] = &[&[2], &[0, 1, 2]];
+        let courses = [0, 1, 2];
+        assert_eq!(
+            combine(rankings, &courses, AggregationMethod::Borda),
+            [0, 2, 1]
+        );
+        assert_eq!(
+            combine(rankings, &courses, AggregationMethod::AverageRank),
+            [0, 1, 2]
+        );
+
+        // Courses nobody ranked go last, and courses not being ranked are ignored
+        let rankings: &[&[u32]] = &[&[9, 2, 1]];
+        let average = combine(rankings, &[0, 1, 2], AggregationMethod::AverageRank);
+        assert_eq!(average, [2, 1, 0]);
+    }
+
+    #[test]
+    fn contested_courses_come_first() {
+        let first = ids(&[0, 1, 2]);
+        let second = ids(&[2, 1, 0]);
+        let contested = contested(&[&first, &second]);
+
+        let order: Vec<u32> = contested.iter().map(|c| c.course.0).collect();
+        assert_eq!(order, [0, 2, 1]);
+        assert_eq!((contested[0].best, contested[0].worst), (1, 3));
+        assert!((contested[0].std_dev - 1.0).abs() < f64::EPSILON);
+        assert!(contested[2].std_dev.abs() < f64::EPSILON);
+    }
+}
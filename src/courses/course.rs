@@ -1,11 +1,12 @@
 //! Module defining the `Course` struct and its components.
 
 use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
 
 /// Game from which a given `Course` originated.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[allow(missing_docs)]
 #[non_exhaustive]
 pub enum Origin {
@@ -37,9 +38,39 @@ impl Display for Origin {
     }
 }
 
+impl FromStr for Origin {
+    type Err = String;
+
+    /// Parse an origin from its displayed name, e.g. "3DS", or its variant name, e.g. "TDS",
+    /// ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_ref() {
+            "snes" => Ok(Self::SNES),
+            "gba" => Ok(Self::GBA),
+            "n64" => Ok(Self::N64),
+            "gcn" => Ok(Self::GCN),
+            "ds" => Ok(Self::DS),
+            "wii" => Ok(Self::Wii),
+            "3ds" | "tds" => Ok(Self::TDS),
+            "tour" => Ok(Self::Tour),
+            "mk8" => Ok(Self::MK8),
+            _ => Err(format!("Unknown origin '{s}'")),
+        }
+    }
+}
+
+/// Number of courses in a cup.
+pub const CUP_SIZE: u8 = 4;
+
+/// Number of rows of cups in the selection screen.
+pub const ROWS: u8 = 4;
+
+/// Number of columns of cups in the selection screen.
+pub const COLUMNS: u8 = 6;
+
 /// Defines a coordinate in the selection screen: row and column give a cup, and position is the
 /// number of the course in that cup.
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Coord {
     row: u8,
     col: u8,
@@ -48,10 +79,35 @@ pub struct Coord {
 
 impl Coord {
     /// Create a new coordinate.
-    #[allow(unused)]
     pub fn new(row: u8, col: u8, pos: u8) -> Self {
         Coord { row, col, pos }
     }
+
+    /// Get the row of the course's cup.
+    #[inline]
+    pub fn row(&self) -> u8 {
+        self.row
+    }
+
+    /// Get the column of the course's cup.
+    #[inline]
+    pub fn col(&self) -> u8 {
+        self.col
+    }
+
+    /// Get the position of the course in its cup.
+    #[inline]
+    pub fn pos(&self) -> u8 {
+        self.pos
+    }
+
+    /// Check whether the coordinate is on the selection screen: row between 1 and `ROWS`,
+    /// column between 1 and `COLUMNS`, and position between 1 and `CUP_SIZE`.
+    pub fn is_valid(&self) -> bool {
+        (1..=ROWS).contains(&self.row)
+            && (1..=COLUMNS).contains(&self.col)
+            && (1..=CUP_SIZE).contains(&self.pos)
+    }
 }
 
 impl Display for Coord {
@@ -60,11 +116,62 @@ impl Display for Coord {
     }
 }
 
+impl FromStr for Coord {
+    type Err = String;
+
+    /// Parse a coordinate as displayed, e.g. "3-1-4".
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<u8> = s
+            .trim()
+            .split('-')
+            .map(|part| part.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Invalid coordinate '{s}'"))?;
+        match parts[..] {
+            [row, col, pos] => Ok(Coord { row, col, pos }),
+            _ => Err(format!("Invalid coordinate '{s}', expected row-col-pos")),
+        }
+    }
+}
+
+/// Stable identifier of a `Course`. Unlike a course's position in a list, its id never changes,
+/// so history and other saved state can refer to the course no matter how the list is reordered
+/// or extended. Ids are unique within a `CourseList` or `GameDefinition`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct CourseId(pub u32);
+
+impl Display for CourseId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for CourseId {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.trim()
+            .parse()
+            .map(CourseId)
+            .map_err(|_| format!("Invalid course id '{s}'"))
+    }
+}
+
 /// A course.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Course {
+    /// The course's id. Saves from before ids existed are given ids when loaded. See
+    /// `CourseList::restore_save`.
+    #[serde(default)]
+    pub id: CourseId,
     /// The course's name, e.g. "Rainbow Road."
     pub name: String,
+    /// Other names the course goes by, e.g. community abbreviations like "DKJ" or "rMC".
+    #[serde(default)]
+    pub aliases: Vec<String>,
     /// The game from which the course originated.
     pub origin: Origin,
     /// The course's position in the selection screen.
@@ -73,21 +180,40 @@ pub struct Course {
     /// descending, and start at 1. If there are N courses in the game, 1 is the best and N is the
     /// worst.
     pub rank: usize,
+    /// Free-form labels for the course, e.g. "night" or "water".
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 impl Course {
     /// Create a new course.
-    #[allow(unused)]
-    pub fn new(rank: usize, coord: Coord, origin: Origin, name: &str) -> Self {
+    pub fn new(id: CourseId, rank: usize, coord: Coord, origin: Origin, name: &str) -> Self {
         Course {
+            id,
             name: name.to_owned(),
+            aliases: Vec::new(),
             origin,
             coord,
             rank,
+            tags: Vec::new(),
         }
     }
 }
 
+/// Find the course with the given id.
+pub fn find_by_id(courses: &[Course], id: CourseId) -> Option<&Course> {
+    courses.iter().find(|c| c.id == id)
+}
+
+/// Get an id that no course in `courses` has: one past the highest id in use.
+pub fn unused_id(courses: &[Course]) -> CourseId {
+    courses
+        .iter()
+        .map(|c| CourseId(c.id.0.saturating_add(1)))
+        .max()
+        .unwrap_or_default()
+}
+
 impl Display for Course {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(
@@ -3,31 +3,118 @@
 
 #![allow(clippy::result_unit_err)]
 
-use std::collections::BTreeSet;
-use std::fs::{self, File};
-use std::io::{self, Write};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io;
+use std::ops::Index;
 use std::path::PathBuf;
+use std::time::{Instant, SystemTime};
 
 use rand::seq::{IndexedRandom, IteratorRandom};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::SAVES_DIR;
+use crate::lock::SaveLock;
+use crate::saves::{self, sanitize_save_name};
+use crate::{SAVES_DIR, write_atomic};
 
-use super::course::Course;
+use super::aggregate::{self, AggregationMethod, Contested};
+use super::course::{Coord, Course, CourseId, Origin, find_by_id, unused_id};
+use super::edit::{CourseEdit, EditError};
+use super::event::Event;
+use super::game::{self, GameDefinition, GameMerge, GameRef};
 use super::history::Action;
 use super::history::History;
+use super::observer::{Change, Observer, Observers, Snapshot};
+use super::player::{Player, PoolMode};
+use super::prix::{Prix, PrixEntry};
+use super::ranking::RankingSession;
+use super::ratings::{RatingConfig, Ratings};
+use super::results::{self, CourseLeader, PointTable, RaceResult, Standing};
+use super::search::{self, SearchMatch};
+use super::session::Session;
+use super::tiers::{Tier, TierStrategy};
+use super::tournament::{Format, Match, Participant, Tournament, TournamentError};
+use super::veto::VetoPhase;
+
+/// Version of the save format written by this version of the program. Version 0 saves, from
+/// before courses had ids, referred to courses by their index in `CourseList::courses`. Version 1
+/// saves had no race log.
+const SAVE_VERSION: u32 = 2;
 
 /// Main course list struct.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CourseList {
-    /// List of all courses in the game. This should generally be set once and then left alone.
-    /// Courses should be listed by rank in descending order.
+    /// List of all courses in the game. This should generally be set once and then left alone,
+    /// apart from rewriting ranks. Courses should be listed by rank in descending order, but
+    /// re-ranking does not reorder the list, so use `Course::rank` when ordering matters. If the
+    /// list is linked to a game, the courses are kept in line with its definition when the list
+    /// is loaded, and only their ranks are taken from the save.
+    ///
+    /// Everything else refers to courses by `Course::id`, never by their position in this list,
+    /// so ids must be unique and must not change. Use `get_course` or indexing by id to look a
+    /// course up.
     pub courses: Vec<Course>,
     /// Name of the save file as found in `SAVES_DIR`. Should be a relative path ending in .json.
     /// For example, if there is a save `SAVES_DIR/save.json`, this field would be "save.json".
     pub save_name: PathBuf,
-    current: BTreeSet<usize>,
+    #[serde(default)]
+    version: u32,
+    current: BTreeSet<CourseId>,
     history: History,
+    #[serde(default)]
+    prix: Option<Prix>,
+    #[serde(default)]
+    ranking: Option<RankingSession>,
+    #[serde(default)]
+    players: Vec<Player>,
+    #[serde(default)]
+    pool_mode: PoolMode,
+    #[serde(default)]
+    veto: Option<VetoPhase>,
+    #[serde(default)]
+    prix_count: usize,
+    #[serde(default)]
+    results: Vec<RaceResult>,
+    #[serde(default)]
+    point_table: PointTable,
+    #[serde(default)]
+    tournament: Option<Tournament>,
+    #[serde(default)]
+    tournament_count: usize,
+    #[serde(default)]
+    sessions: Vec<Session>,
+    #[serde(default)]
+    race_log: Vec<CourseId>,
+    #[serde(default)]
+    next_course_id: CourseId,
+    #[serde(default)]
+    game: Option<GameRef>,
+    #[serde(default)]
+    retired: BTreeSet<CourseId>,
+    #[serde(skip)]
+    game_merge: Option<GameMerge>,
+    #[serde(skip)]
+    game_error: Option<String>,
+    #[serde(skip)]
+    game_ids: BTreeSet<CourseId>,
+    #[serde(skip)]
+    observers: Observers,
+    #[serde(skip)]
+    observed: Snapshot,
+    #[serde(skip)]
+    observer_errors: Vec<io::Error>,
+    #[serde(skip)]
+    generation: u64,
+    #[serde(skip)]
+    saved_generation: Cell<u64>,
+    #[serde(skip)]
+    disk_modified: Cell<Option<SystemTime>>,
+    #[serde(skip)]
+    lock: RefCell<Option<SaveLock>>,
+    #[serde(skip)]
+    read_only: bool,
 }
 
 impl CourseList {
@@ -36,124 +123,1652 @@ impl CourseList {
         CourseList {
             courses: Vec::new(),
             save_name: save_name.into(),
+            version: SAVE_VERSION,
             current: BTreeSet::new(),
             history: History::default(),
+            prix: None,
+            ranking: None,
+            players: Vec::new(),
+            pool_mode: PoolMode::default(),
+            veto: None,
+            prix_count: 0,
+            results: Vec::new(),
+            point_table: PointTable::default(),
+            tournament: None,
+            tournament_count: 0,
+            sessions: Vec::new(),
+            race_log: Vec::new(),
+            next_course_id: CourseId(0),
+            game: None,
+            retired: BTreeSet::new(),
+            game_merge: None,
+            game_error: None,
+            game_ids: BTreeSet::new(),
+            observers: Observers::default(),
+            observed: Snapshot::default(),
+            observer_errors: Vec::new(),
+            generation: 0,
+            saved_generation: Cell::new(0),
+            disk_modified: Cell::new(None),
+            lock: RefCell::new(None),
+            read_only: false,
         }
     }
 
+    /// Create a `CourseList` with the given `save_name` and courses, all of them active. The
+    /// courses' ids should be unique.
+    pub fn from_courses(save_name: impl Into<PathBuf>, courses: Vec<Course>) -> Self {
+        let mut list = Self::new(save_name);
+        list.current = courses.iter().map(|c| c.id).collect();
+        list.courses = courses;
+        list
+    }
+
+    /// Create a `CourseList` with the given `save_name`, linked to a game and using its courses,
+    /// all of them active.
+    pub fn from_game(save_name: impl Into<PathBuf>, game: &GameDefinition) -> Self {
+        let mut list = Self::from_courses(save_name, game.courses.clone());
+        list.game = Some(game.game_ref());
+        list.game_ids = game.courses.iter().map(|c| c.id).collect();
+        list
+    }
+
+    /// Create a `CourseList` with the same game and courses as this one, including ranks, with
+    /// every course in the game active and nothing else carried over.
+    #[must_use]
+    pub fn blank_copy(&self, save_name: impl Into<PathBuf>) -> Self {
+        let mut list = Self::from_courses(save_name, self.courses.clone());
+        list.game.clone_from(&self.game);
+        list.game_ids.clone_from(&self.game_ids);
+        list.current.retain(|i| !self.retired.contains(i));
+        list.retired.clone_from(&self.retired);
+        list
+    }
+
     /// Restore an existing, saved `CourseList` in the `SAVES_DIR` with the filename `save_name`.
     /// `save_name` should be a `*.json` path relative to `SAVES_DIR`. For example, if there is a
     /// save named `my_save`, you can load it by calling this function with `my_save.json`.
     ///
+    /// The save is locked before it is read, and for as long as the list exists, so other
+    /// sessions cannot use it. See `lock::SaveLock`. The list keeps `save_name` as its save name,
+    /// even if the save file stores another.
+    ///
+    /// If the list is linked to a game, courses saved as just their id and rank are rebuilt from
+    /// the game's definition, and the list is reconciled with it. See `merge_game` and
+    /// `get_game_merge`. Saves from before lists were linked to games are linked to the built-in
+    /// game if all of their courses are found in it. Saves from before courses had ids give each
+    /// course its index as its id, which keeps their history valid.
+    ///
+    /// If the game's definition cannot be loaded, the list is opened anyway, unlinked and read
+    /// only, with the courses saved as just their id and rank under placeholder names. Saving
+    /// it would overwrite the save with the placeholders. See `get_game_error`.
+    ///
     /// # Errors
     /// - The given `save_name` does not exist in `SAVES_DIR`.
     /// - The save file cannot be deserialized into a valid `CourseList`.
+    /// - The save is in use by another session. The error is of kind `ErrorKind::ResourceBusy`,
+    ///   and `restore_save_read_only` can still open it.
     pub fn restore_save(save_name: impl Into<PathBuf>) -> io::Result<Self> {
-        let save_path = SAVES_DIR.join(save_name.into());
-        let data = fs::read_to_string(save_path)?;
-        Ok(serde_json::from_str(&data)?)
+        let save_name = save_name.into();
+        // Locked before reading, so no other session can change the save in between
+        let lock = SaveLock::acquire(&SAVES_DIR.join(&save_name))?;
+        let mut list = Self::restore_save_read_only(save_name)?;
+        list.read_only = list.game_error.is_some();
+        *list.lock.get_mut() = Some(lock);
+        Ok(list)
+    }
+
+    /// Restore a saved `CourseList` like `restore_save`, without locking it. The list is read
+    /// only: it can be changed, but not saved.
+    ///
+    /// # Errors
+    /// - The given `save_name` does not exist in `SAVES_DIR`.
+    /// - The save file cannot be deserialized into a valid `CourseList`.
+    pub fn restore_save_read_only(save_name: impl Into<PathBuf>) -> io::Result<Self> {
+        let save_name = save_name.into();
+        let data = fs::read_to_string(SAVES_DIR.join(&save_name))?;
+        let mut list = Self::from_save_data(&data)?;
+        // The list belongs to the file it was read from, whatever name is stored inside it
+        list.save_name = save_name;
+        list.read_only = true;
+        list.record_disk_modified();
+        let legacy = list.version < 1;
+        list.migrate();
+        list.sync_game(legacy)?;
+        Ok(list)
+    }
+
+    // Deserialize a save, first rebuilding the courses of a linked game that were saved as just
+    // their id and rank from the game's definition. See `save_data`. If the definition cannot be
+    // loaded, the list is unlinked from the game instead, and the error is kept
+    pub(crate) fn from_save_data(data: &str) -> io::Result<Self> {
+        let mut data: Value = serde_json::from_str(data)?;
+        let game_id = data
+            .pointer("/game/id")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        let mut game_error = None;
+        if let Some(game_id) = game_id
+            && let Some(courses) = data.get_mut("courses").and_then(Value::as_array_mut)
+        {
+            let game = GameDefinition::load(&game_id)
+                .map_err(|e| game_error = Some(format!("Could not load game '{game_id}': {e}")))
+                .ok();
+            for course in courses.iter_mut().filter_map(Value::as_object_mut) {
+                if course.contains_key("name") {
+                    continue;
+                }
+                let id = course.get("id").map(CourseId::deserialize).transpose()?;
+                let id = id.unwrap_or_default();
+
+                // A course the definition dropped since is kept under a placeholder, and is
+                // retired when the list is reconciled with the game
+                let defined = game.iter().flat_map(|g| &g.courses).find(|c| c.id == id);
+                let defined = defined.cloned();
+                let defined = defined.unwrap_or_else(|| {
+                    Course::new(
+                        id,
+                        0,
+                        Coord::new(0, 0, 0),
+                        Origin::MK8,
+                        &format!("Course {id}"),
+                    )
+                });
+                let Value::Object(defined) = serde_json::to_value(defined)? else {
+                    continue;
+                };
+                for (key, value) in defined {
+                    course.entry(key).or_insert(value);
+                }
+            }
+        }
+
+        let mut list: Self = serde_json::from_value(data)?;
+        if game_error.is_some() {
+            list.game = None;
+            list.game_error = game_error;
+        }
+        Ok(list)
+    }
+
+    // Bring a list loaded from an older save format up to date. Before version 1, courses were
+    // referred to by index, so each course takes its index as its id. Before version 2, there
+    // was no race log, so it starts from the removals still in history
+    fn migrate(&mut self) {
+        if self.version < 1 {
+            for (i, course) in self.courses.iter_mut().enumerate() {
+                course.id = CourseId(u32::try_from(i).unwrap_or(u32::MAX));
+            }
+        }
+        if self.version < 2 {
+            self.race_log = self.history.recent_removed().collect();
+            self.race_log.reverse();
+        }
+        self.version = SAVE_VERSION;
+    }
+
+    // Reconcile the list with its game's definition, linking lists from before games to the
+    // built-in game when it has all of their courses. Newer lists without a game were unlinked
+    // on purpose, e.g. by editing their courses, so they are left alone
+    fn sync_game(&mut self, legacy: bool) -> io::Result<()> {
+        let game = if let Some(game) = &self.game {
+            GameDefinition::load(&game.id)?
+        } else {
+            if !legacy {
+                return Ok(());
+            }
+            let builtin = GameDefinition::load(game::BUILTIN_ID)?;
+            if !self.courses.iter().all(|c| builtin.find(c).is_some()) {
+                return Ok(());
+            }
+            builtin
+        };
+
+        let merge = self.merge_game(&game);
+        self.game_merge = (!merge.is_empty()).then_some(merge);
+        Ok(())
     }
 
-    /// Serialize and save the list to `SAVES_DIR/self.save_name`.
+    /// Reconcile the list's courses with a game definition and link the list to it. Courses are
+    /// matched by id, as long as their name or their spot on the selection screen also agrees, so
+    /// that the ids of an unrelated list are not trusted. Failing that, they are matched by name,
+    /// and then by spot, in which case they were renamed. Matched courses take their data from
+    /// the definition, apart from their id and rank.
+    ///
+    /// Course ids never change, so history and other state stay valid. Courses new to the list
+    /// are added at the end, active and ranked below every other course, keeping their id from
+    /// the definition unless the list already uses it. Courses missing from the definition are
+    /// retired: they stay in the list for their history, but are never active again unless the
+    /// definition brings them back.
+    pub fn merge_game(&mut self, game: &GameDefinition) -> GameMerge {
+        let mut merge = GameMerge {
+            from: self.game.clone(),
+            to: game.game_ref(),
+            added: Vec::new(),
+            retired: Vec::new(),
+            renamed: Vec::new(),
+        };
+
+        // Match by id, then by name, so a course that is renamed to the name of another is not
+        // taken, then by spot
+        let same_spot = |a: &Course, b: &Course| a.coord == b.coord && a.origin == b.origin;
+        let same = |pass, a: &Course, b: &Course| match pass {
+            0 => a.id == b.id && (a.name == b.name || same_spot(a, b)),
+            1 => a.name == b.name,
+            _ => same_spot(a, b),
+        };
+        let mut matches: Vec<Option<usize>> = vec![None; self.courses.len()];
+        let mut taken = vec![false; game.courses.len()];
+        for pass in 0..3 {
+            for (i, course) in self.courses.iter().enumerate() {
+                if matches[i].is_some() {
+                    continue;
+                }
+                let found = (0..game.courses.len())
+                    .find(|&j| !taken[j] && same(pass, course, &game.courses[j]));
+                if let Some(j) = found {
+                    matches[i] = Some(j);
+                    taken[j] = true;
+                }
+            }
+        }
+
+        self.game_ids.clear();
+        for (i, found) in matches.into_iter().enumerate() {
+            let id = self.courses[i].id;
+            let Some(j) = found else {
+                if self.retired.insert(id) {
+                    self.current.remove(&id);
+                    merge.retired.push(id);
+                }
+                continue;
+            };
+
+            let course = &mut self.courses[i];
+            let defined = &game.courses[j];
+            if id == defined.id {
+                self.game_ids.insert(id);
+            }
+            if course.name != defined.name {
+                merge.renamed.push((id, course.name.clone()));
+            }
+            course.name.clone_from(&defined.name);
+            course.aliases.clone_from(&defined.aliases);
+            course.origin = defined.origin;
+            course.coord = defined.coord;
+            course.tags.clone_from(&defined.tags);
+
+            if self.retired.remove(&id) {
+                self.current.insert(id);
+                merge.added.push(id);
+            }
+        }
+
+        let mut rank = self.courses.iter().map(|c| c.rank).max().unwrap_or(0);
+        for (j, defined) in game.courses.iter().enumerate() {
+            if taken[j] {
+                continue;
+            }
+            rank += 1;
+            let mut course = defined.clone();
+            course.rank = rank;
+            if self.get_course(course.id).is_some() {
+                course.id = self.new_course_id();
+            } else {
+                self.game_ids.insert(course.id);
+            }
+            self.current.insert(course.id);
+            merge.added.push(course.id);
+            self.courses.push(course);
+        }
+
+        self.game = Some(merge.to.clone());
+        if !merge.is_empty() {
+            self.generation += 1;
+        }
+        merge
+    }
+
+    /// Get the game the list is linked to, if any.
+    #[inline]
+    pub fn get_game(&self) -> Option<&GameRef> {
+        self.game.as_ref()
+    }
+
+    /// Get the changes made when the list was reconciled with its game on loading, if anything
+    /// changed.
+    #[inline]
+    pub fn get_game_merge(&self) -> Option<&GameMerge> {
+        self.game_merge.as_ref()
+    }
+
+    /// Get the reason the list's game could not be loaded, if it could not. Such a list is
+    /// unlinked and read only, with placeholder names for the game's courses. See
+    /// `restore_save`.
+    #[inline]
+    pub fn get_game_error(&self) -> Option<&str> {
+        self.game_error.as_deref()
+    }
+
+    /// Check whether a course was retired because its game no longer has it. See `merge_game`.
+    #[inline]
+    pub fn is_retired(&self, course: CourseId) -> bool {
+        self.retired.contains(&course)
+    }
+
+    /// Get the course with the given id, if there is one. Indexing the list by id does the same,
+    /// but panics if there is no such course.
+    pub fn get_course(&self, id: CourseId) -> Option<&Course> {
+        find_by_id(&self.courses, id)
+    }
+
+    /// Put a new course in the list, active, at the course's rank. Courses at that rank or below
+    /// move down one rank. The course is given an id no course in the list has had, which is
+    /// returned. The list is
+    /// unlinked from its game, since the game no longer describes it, until the creation is
+    /// undone.
     ///
     /// # Errors
+    /// - If the course's name is blank.
+    /// - If the coordinate is not on the selection screen. See `Coord::is_valid`.
+    /// - If another course in the game has the same coordinate.
+    /// - If the rank is not between 1 and one past the lowest rank.
+    pub fn create_course(&mut self, mut course: Course) -> Result<CourseId, EditError> {
+        course.name = course.name.trim().to_string();
+        if course.name.is_empty() {
+            return Err(EditError::EmptyName);
+        }
+        self.check_coord(course.coord, None)?;
+        let max_rank = self.courses.len() + 1;
+        if !(1..=max_rank).contains(&course.rank) {
+            return Err(EditError::RankOutOfRange(max_rank));
+        }
+
+        course.id = self.new_course_id();
+        let id = course.id;
+        let position = self
+            .courses
+            .iter()
+            .position(|c| c.rank >= course.rank)
+            .unwrap_or(self.courses.len());
+        self.insert_course(course.clone(), position, true);
+        let unlinked = self.game.take();
+        self.history.push(Action::Create { course, unlinked });
+        self.emit(&Event::CourseCreated { course: id });
+        Ok(id)
+    }
+
+    /// Change a course's name, aliases, origin, coordinate, rank or tags. When the rank changes,
+    /// the courses between the old and new rank move one rank to make room. Changing anything but
+    /// the rank unlinks the list from its game, like `create_course`.
+    ///
+    /// # Errors
+    /// - If there is no course with the given id.
+    /// - If the new name is blank.
+    /// - If the new coordinate is not on the selection screen. See `Coord::is_valid`.
+    /// - If another course in the game has the new coordinate.
+    /// - If the new rank is not between 1 and the lowest rank.
+    pub fn edit_course(&mut self, id: CourseId, edit: CourseEdit) -> Result<(), EditError> {
+        let before = self.get_course(id).ok_or(EditError::NoSuchCourse)?.clone();
+        let mut after = before.clone();
+        if let Some(name) = edit.name {
+            name.trim().clone_into(&mut after.name);
+            if after.name.is_empty() {
+                return Err(EditError::EmptyName);
+            }
+        }
+        if let Some(coord) = edit.coord {
+            self.check_coord(coord, Some(id))?;
+            after.coord = coord;
+        }
+        if let Some(rank) = edit.rank {
+            if !(1..=self.courses.len()).contains(&rank) {
+                return Err(EditError::RankOutOfRange(self.courses.len()));
+            }
+            after.rank = rank;
+        }
+        after.aliases = edit.aliases.unwrap_or(after.aliases);
+        after.origin = edit.origin.unwrap_or(after.origin);
+        after.tags = edit.tags.unwrap_or(after.tags);
+
+        // `Course` equality only compares ranks
+        let data_changed = after.name != before.name
+            || after.aliases != before.aliases
+            || after.origin != before.origin
+            || after.coord != before.coord
+            || after.tags != before.tags;
+        self.replace_course(&after);
+        let unlinked = if data_changed { self.game.take() } else { None };
+        self.history.push(Action::Edit {
+            before,
+            after,
+            unlinked,
+        });
+        self.emit(&Event::CourseEdited { course: id });
+        Ok(())
+    }
+
+    /// Take a course out of the list. Courses below it move up one rank. The list is unlinked
+    /// from its game, like `create_course`. Courses that anything but history still refers to
+    /// cannot be deleted: remove their results first, or wait for the prix, veto phase, ranking
+    /// or tournament to end.
+    ///
+    /// # Errors
+    /// - If there is no course with the given id.
+    /// - If the course has results, is ranked or banned by a player, or is part of the prix,
+    ///   veto phase, ranking or tournament in progress.
+    pub fn delete_course(&mut self, id: CourseId) -> Result<Course, EditError> {
+        let position = self
+            .courses
+            .iter()
+            .position(|c| c.id == id)
+            .ok_or(EditError::NoSuchCourse)?;
+        if self.is_referenced(id) {
+            return Err(EditError::InUse);
+        }
+
+        let active = self.current.contains(&id);
+        let course = self.take_course(id).ok_or(EditError::NoSuchCourse)?;
+        let unlinked = self.game.take();
+        self.history.push(Action::Delete {
+            course: course.clone(),
+            position,
+            active,
+            unlinked,
+        });
+        self.emit(&Event::CourseDeleted {
+            course: id,
+            name: course.name.clone(),
+        });
+        Ok(course)
+    }
+
+    // Whether anything that outlives an undo refers to the course
+    fn is_referenced(&self, id: CourseId) -> bool {
+        self.results.iter().any(|r| r.course == id)
+            || self
+                .players
+                .iter()
+                .any(|p| p.ranking.contains(&id) || p.banned.contains(&id))
+            || self
+                .prix
+                .as_ref()
+                .is_some_and(|p| p.remaining.iter().chain(&p.raced).any(|e| e.course == id))
+            || self.veto.as_ref().is_some_and(|v| {
+                v.candidates.contains(&id) || v.strikes.iter().any(|&(_, c)| c == id)
+            })
+            || self.ranking.as_ref().is_some_and(|r| r.contains(id))
+            || self
+                .tournament
+                .as_ref()
+                .is_some_and(|t| t.used_courses().contains(&id))
+    }
+
+    // Check that `coord` is on the selection screen, and that no course in the game other than
+    // `except` is at it
+    fn check_coord(&self, coord: Coord, except: Option<CourseId>) -> Result<(), EditError> {
+        if !coord.is_valid() {
+            return Err(EditError::InvalidCoord(coord));
+        }
+        match self
+            .courses
+            .iter()
+            .filter(|c| Some(c.id) != except && !self.retired.contains(&c.id))
+            .find(|c| c.coord == coord)
+        {
+            Some(taken) => Err(EditError::CoordTaken {
+                coord,
+                name: taken.name.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Get an id no course has had before. Ids are never reused, even once their course is
+    // deleted, since the race log and results may still refer to them. Saves from before the
+    // counter was kept fall back on the ids still in the list and its race log
+    fn new_course_id(&mut self) -> CourseId {
+        let in_use = self
+            .race_log
+            .iter()
+            .map(|id| CourseId(id.0.saturating_add(1)));
+        let id = in_use
+            .chain([self.next_course_id, unused_id(&self.courses)])
+            .max()
+            .unwrap_or_default();
+        self.next_course_id = CourseId(id.0.saturating_add(1));
+        id
+    }
+
+    // Put a course in `courses` at `position`, moving courses at its rank or below down a rank
+    fn insert_course(&mut self, course: Course, position: usize, active: bool) {
+        for other in &mut self.courses {
+            if other.rank >= course.rank {
+                other.rank += 1;
+            }
+        }
+        if active {
+            self.current.insert(course.id);
+        }
+        self.courses
+            .insert(position.min(self.courses.len()), course);
+    }
+
+    // Take a course out of `courses`, moving courses below it up a rank
+    fn take_course(&mut self, id: CourseId) -> Option<Course> {
+        let position = self.courses.iter().position(|c| c.id == id)?;
+        let course = self.courses.remove(position);
+        self.current.remove(&id);
+        for other in &mut self.courses {
+            if other.rank > course.rank {
+                other.rank -= 1;
+            }
+        }
+        Some(course)
+    }
+
+    // Overwrite the course with the same id, moving the courses between its old and new rank
+    fn replace_course(&mut self, course: &Course) {
+        let Some(old_rank) = self.get_course(course.id).map(|c| c.rank) else {
+            return;
+        };
+        for other in &mut self.courses {
+            if other.id == course.id {
+                *other = course.clone();
+            } else if course.rank < old_rank && (course.rank..old_rank).contains(&other.rank) {
+                other.rank += 1;
+            } else if course.rank > old_rank && (old_rank + 1..=course.rank).contains(&other.rank) {
+                other.rank -= 1;
+            }
+        }
+    }
+
+    // Serialize the list for saving. Courses of a linked game that have the same id in the
+    // game's definition are saved as just their id and rank, and rebuilt from the definition on
+    // loading. See `from_save_data`
+    fn save_data(&self) -> serde_json::Result<Value> {
+        let mut data = serde_json::to_value(self)?;
+        if self.game.is_some()
+            && let Some(courses) = data.get_mut("courses").and_then(Value::as_array_mut)
+        {
+            for course in courses.iter_mut().filter_map(Value::as_object_mut) {
+                let id = course.get("id").map(CourseId::deserialize).transpose()?;
+                if id.is_some_and(|id| self.game_ids.contains(&id)) {
+                    course.retain(|key, _| key == "id" || key == "rank");
+                }
+            }
+        }
+        Ok(data)
+    }
+
+    /// Check whether the list was opened read only. See `restore_save_read_only`.
+    #[inline]
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    // Lock the save at `save_path`, unless it is already locked. A lock on a previous save path
+    // is released once the new one is held
+    pub(crate) fn ensure_locked(&self) -> io::Result<()> {
+        let path = self.save_path();
+        let mut lock = self.lock.borrow_mut();
+        if lock.as_ref().is_none_or(|l| l.save_path() != path) {
+            *lock = Some(SaveLock::acquire(&path)?);
+        }
+        Ok(())
+    }
+
+    /// Serialize and save the list to `SAVES_DIR/self.save_name`. The file is replaced
+    /// atomically, so a crash while saving leaves the previous save intact. The save is locked
+    /// first if it is not already, as with `restore_save`.
+    ///
+    /// # Errors
+    /// - If the list is read only. The error is of kind `ErrorKind::PermissionDenied`.
+    /// - If the save is in use by another session. The error is of kind
+    ///   `ErrorKind::ResourceBusy`.
     /// - If the `CourseList` cannot be serialized to a JSON string.
-    /// - If `SAVES_DIR/self.save_name` cannot be opened for writing.
-    /// - If `SAVES_DIR/self.save_name` is opened for writing, but writing failed.
+    /// - If `SAVES_DIR/self.save_name` cannot be written.
     pub fn dump_list(&self) -> io::Result<()> {
+        if self.read_only {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the list was opened read only",
+            ));
+        }
+        self.ensure_locked()?;
         let path = self.save_path();
 
-        let data = serde_json::to_string_pretty(&self)?;
-        let mut file = File::create(path)?;
-        file.write_all(data.as_bytes())?;
+        let data = serde_json::to_string_pretty(&self.save_data()?)?;
+        write_atomic(&path, data.as_bytes())?;
+        self.saved_generation.set(self.generation);
+        self.record_disk_modified();
         Ok(())
     }
 
+    /// Move the list to a new save and save it there. The name is sanitized with
+    /// `saves::sanitize_save_name`. The previous save is left as it was last saved, and its lock
+    /// is released. A read only list can be saved this way, after which it is no longer read
+    /// only. Returns the sanitized name.
+    ///
+    /// # Errors
+    /// - If `save_name` is not a valid save name. See `saves::sanitize_save_name`.
+    /// - If the new save is in use. The error is of kind `ErrorKind::ResourceBusy`.
+    /// - If the save already exists and `overwrite` is false. The error is of kind
+    ///   `ErrorKind::AlreadyExists`.
+    /// - If the list cannot be saved. See `dump_list`.
+    pub fn save_as(&mut self, save_name: &str, overwrite: bool) -> io::Result<PathBuf> {
+        let save_name = sanitize_save_name(save_name)?;
+        let path = SAVES_DIR.join(&save_name);
+        let renamed = save_name != self.save_name;
+        // Locked before checking, so no other session can create the save in between
+        let lock = renamed.then(|| SaveLock::acquire(&path)).transpose()?;
+        if !overwrite && renamed && path.try_exists()? {
+            return Err(saves::already_exists(&save_name));
+        }
+
+        let old = (
+            std::mem::replace(&mut self.save_name, save_name.clone()),
+            self.read_only,
+            lock.map(|lock| self.lock.replace(Some(lock))),
+        );
+        self.read_only = false;
+        if let Err(e) = self.dump_list() {
+            let (save_name, read_only, lock) = old;
+            (self.save_name, self.read_only) = (save_name, read_only);
+            if let Some(lock) = lock {
+                *self.lock.get_mut() = lock;
+            }
+            return Err(e);
+        }
+        Ok(save_name)
+    }
+
+    /// Save a copy of the list, including its history, to a new save, leaving the list itself on
+    /// its current save. The name is sanitized with `saves::sanitize_save_name`. Returns the
+    /// sanitized name.
+    ///
+    /// # Errors
+    /// - If `save_name` is not a valid save name, or is the list's own save. See
+    ///   `saves::sanitize_save_name`.
+    /// - If the new save is in use. The error is of kind `ErrorKind::ResourceBusy`.
+    /// - If the save already exists and `overwrite` is false. The error is of kind
+    ///   `ErrorKind::AlreadyExists`.
+    /// - If the copy cannot be serialized or written.
+    pub fn fork(&self, save_name: &str, overwrite: bool) -> io::Result<PathBuf> {
+        let save_name = sanitize_save_name(save_name)?;
+        if save_name == self.save_name {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is the save this list is using", save_name.display()),
+            ));
+        }
+
+        let path = SAVES_DIR.join(&save_name);
+        let _lock = SaveLock::acquire(&path)?;
+        if !overwrite && path.try_exists()? {
+            return Err(saves::already_exists(&save_name));
+        }
+
+        let mut data = self.save_data()?;
+        saves::set_save_name(&mut data, &save_name);
+        write_atomic(&path, serde_json::to_string_pretty(&data)?.as_bytes())?;
+        Ok(save_name)
+    }
+
+    // Remember when the save file was last modified, to detect changes made by something else
+    fn record_disk_modified(&self) {
+        let modified = fs::metadata(self.save_path()).and_then(|m| m.modified());
+        self.disk_modified.set(modified.ok());
+    }
+
+    /// Check whether the list has changed since it was loaded or last saved.
+    #[inline]
+    pub fn is_dirty(&self) -> bool {
+        self.generation != self.saved_generation.get()
+    }
+
+    /// Get the generation of the list: a counter that goes up with every change made to it since
+    /// it was created or loaded. Comparing generations tells whether anything changed in between.
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Check whether the save file was modified by something else since the list was loaded from
+    /// or saved to it, in which case saving would overwrite those changes. Returns false if the
+    /// list was never loaded or saved, or if the save file no longer exists.
+    ///
+    /// # Errors
+    /// - If the save file's metadata cannot be read.
+    pub fn modified_externally(&self) -> io::Result<bool> {
+        let Some(recorded) = self.disk_modified.get() else {
+            return Ok(false);
+        };
+
+        match fs::metadata(self.save_path()) {
+            Ok(metadata) => Ok(metadata.modified()? != recorded),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     // pub fn restore_self(&mut self) -> io::Result<()> {
     //     let data = fs::read_to_string(self.path())?;
     //     *self = serde_json::from_str(&data)?;
     //     Ok(())
     // }
 
-    /// Add a course index back into the list.
-    pub fn add(&mut self, course_i: usize) {
-        self.inner_add(course_i);
-        self.history.push(Action::Add(course_i));
+    /// Register an observer, to be notified of every change made to the list from now on.
+    pub fn subscribe(&mut self, observer: impl Observer + 'static) {
+        if self.observers.0.is_empty() {
+            self.observed = self.snapshot();
+        }
+        self.observers.0.push(Box::new(observer));
     }
 
-    fn inner_add(&mut self, course_i: usize) {
-        self.current.insert(course_i);
+    /// Take a snapshot of the state given to observers as `Change::before`.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            current: self.current.clone(),
+            prix: self.prix.clone(),
+        }
     }
 
-    /// Remove an active course index from the list.
-    pub fn remove(&mut self, course_i: usize) {
-        self.inner_remove(course_i);
-        self.history.push(Action::Remove(course_i));
+    fn emit(&mut self, event: &Event) {
+        self.generation += 1;
+        self.record_session(event);
+        if self.observers.0.is_empty() {
+            return;
+        }
+
+        let after = self.snapshot();
+        let before = std::mem::replace(&mut self.observed, after);
+        let mut observers = std::mem::take(&mut self.observers);
+        let change = Change {
+            event,
+            before: &before,
+            after: self,
+        };
+        let errors: Vec<io::Error> = observers
+            .0
+            .iter_mut()
+            .filter_map(|observer| observer.on_change(&change).err())
+            .collect();
+        self.observers = observers;
+        self.observer_errors.extend(errors);
     }
 
-    fn inner_remove(&mut self, course_i: usize) {
-        self.current.remove(&course_i);
+    /// Let observers catch up on work held back from earlier changes, e.g. saving changes an
+    /// `Autosave` has not saved yet. Call this once the time given by `next_flush` has passed,
+    /// and with `closing` set before closing the list. Errors are kept as for changes. See
+    /// `take_observer_errors`.
+    pub fn flush_observers(&mut self, closing: bool) {
+        let mut observers = std::mem::take(&mut self.observers);
+        let errors: Vec<io::Error> = observers
+            .0
+            .iter_mut()
+            .filter_map(|observer| observer.on_flush(self, closing).err())
+            .collect();
+        self.observers = observers;
+        self.observer_errors.extend(errors);
     }
 
-    /// Search the list of active courses by their names. `searched` is case-insensitive.
-    pub fn search_current(&self, searched: &str) -> impl Iterator<Item = usize> {
-        let key = searched.to_lowercase();
-        self.current
+    /// Get the earliest time from which an observer has work to catch up on, if any. See
+    /// `flush_observers`.
+    pub fn next_flush(&self) -> Option<Instant> {
+        self.observers
+            .0
             .iter()
-            .copied()
-            .filter(move |&i| self.courses[i].name.to_lowercase().contains(&key))
+            .filter_map(|observer| observer.flush_due())
+            .min()
     }
 
-    /// Search the list of removed courses by their names. `searched` is case-insensitive.
-    pub fn search_removed(&self, searched: &str) -> impl Iterator<Item = usize> {
-        let key = searched.to_lowercase();
-        self.get_removed()
-            .filter(move |&i| self.courses[i].name.to_lowercase().contains(&key))
+    /// Take the errors observers ran into since this was last called, oldest first. Observers
+    /// cannot undo a change, so their errors are kept here for the caller to report.
+    pub fn take_observer_errors(&mut self) -> Vec<io::Error> {
+        std::mem::take(&mut self.observer_errors)
     }
 
-    /// Get a random active course.
-    pub fn get_random(&self) -> Option<usize> {
-        if self.current.is_empty() {
+    // Extend the latest session to now, or start a new one if it is over
+    fn record_session(&mut self, event: &Event) {
+        let now = Session::now();
+        if self.sessions.last().is_none_or(|s| s.is_over(now)) {
+            self.sessions.push(Session::start(now));
+        }
+
+        if let Some(session) = self.sessions.last_mut() {
+            session.ended = now;
+            if matches!(event, Event::CourseRolled { .. } | Event::PrixRolled { .. }) {
+                session.rolled += 1;
+            }
+        }
+    }
+
+    /// Add a course back into the list.
+    pub fn add(&mut self, course: CourseId) {
+        self.inner_add(course);
+        self.history.push(Action::Add(course));
+        self.emit(&Event::CourseAdded { course });
+    }
+
+    fn inner_add(&mut self, course: CourseId) {
+        self.current.insert(course);
+    }
+
+    /// Remove an active course from the list.
+    pub fn remove(&mut self, course: CourseId) {
+        let action = Action::Remove(course);
+        self.apply_action(&action);
+        self.history.push(action);
+        self.emit(&Event::CourseRemoved { course });
+    }
+
+    fn inner_remove(&mut self, course: CourseId) {
+        self.current.remove(&course);
+    }
+
+    /// Search the list of active courses by name, alias, origin or coordinate, best match
+    /// first. See `search::search`.
+    pub fn search_current(&self, searched: &str) -> Vec<SearchMatch> {
+        search::search(self.current.iter().map(|&id| &self[id]), searched)
+    }
+
+    /// Search the list of removed courses by name, alias, origin or coordinate, best match
+    /// first. See `search::search`.
+    pub fn search_removed(&self, searched: &str) -> Vec<SearchMatch> {
+        search::search(self.get_removed().map(|id| &self[id]), searched)
+    }
+
+    /// Search all courses, active or not, by name, alias, origin or coordinate, best match
+    /// first. See `search::search`.
+    pub fn search_all(&self, searched: &str) -> Vec<SearchMatch> {
+        search::search(&self.courses, searched)
+    }
+
+    /// Get a random course from the pool. See `get_pool`.
+    pub fn get_random(&self) -> Option<CourseId> {
+        self.get_pool().choose(&mut rand::rng())
+    }
+
+    /// Get a random course from the pool and remove it from the list. In `PoolMode::Rotation`,
+    /// the turn then passes to the next present player.
+    pub fn roll(&mut self) -> Option<CourseId> {
+        let course = self.get_random()?;
+        let action = Action::Roll(course);
+        self.apply_action(&action);
+        self.history.push(action);
+        self.emit(&Event::CourseRolled { course });
+        Some(course)
+    }
+
+    /// Get a view of the pool: the active courses that can be rolled given the present players'
+    /// bans and the `PoolMode`. If no players are present, this is every active course.
+    pub fn get_pool(&self) -> impl Iterator<Item = CourseId> {
+        let banned: BTreeSet<CourseId> = match self.pool_mode {
+            PoolMode::Everyone => self
+                .present_players()
+                .flat_map(|p| p.banned.iter().copied())
+                .collect(),
+            PoolMode::Rotation => self
+                .get_picker()
+                .map(|p| p.banned.clone())
+                .unwrap_or_default(),
+        };
+
+        self.get_current().filter(move |i| !banned.contains(i))
+    }
+
+    /// Get the player whose turn it is to pick in `PoolMode::Rotation`. Returns `None` in other
+    /// modes, or if no players are present.
+    pub fn get_picker(&self) -> Option<&Player> {
+        if self.pool_mode != PoolMode::Rotation {
+            return None;
+        }
+
+        let present: Vec<&Player> = self.present_players().collect();
+        if present.is_empty() {
             return None;
         }
+        // Undoing a roll passes the turn back, and resetting the list starts over from the first
+        // present player
+        Some(present[self.history.rolls() % present.len()])
+    }
+
+    /// Set how the present players' bans limit the pool. See `PoolMode`.
+    pub fn set_pool_mode(&mut self, mode: PoolMode) {
+        self.pool_mode = mode;
+        self.emit(&Event::PoolModeChanged);
+    }
+
+    /// Get how the present players' bans limit the pool. See `PoolMode`.
+    #[inline]
+    pub fn get_pool_mode(&self) -> PoolMode {
+        self.pool_mode
+    }
+
+    /// Split the pool into tiers using the given strategy. See `get_pool`.
+    ///
+    /// # Errors
+    /// - If the strategy cannot be applied to the active courses. See `TierStrategy::split`.
+    pub fn get_tiers(&self, strategy: &TierStrategy) -> Result<Vec<Tier>, ()> {
+        strategy.split(&self.courses, self.get_pool())
+    }
+
+    /// Split the pool into tiers using the given strategy, then grab a random course from each
+    /// tier. See `get_pool`.
+    ///
+    /// # Errors
+    /// - If the strategy cannot be applied to the active courses. See `TierStrategy::split`.
+    pub fn get_random_by_tiers(
+        &self,
+        strategy: &TierStrategy,
+    ) -> Result<impl Iterator<Item = (Tier, CourseId)> + use<>, ()> {
+        let mut rng = rand::rng();
+        let res: Vec<(Tier, CourseId)> = self
+            .get_tiers(strategy)?
+            .into_iter()
+            .map(|tier| {
+                // Tiers are never empty, so unwrap() is fine here
+                #[allow(clippy::missing_panics_doc)]
+                let selection = *tier.courses.choose(&mut rng).unwrap();
+                (tier, selection)
+            })
+            .collect();
 
-        self.current.iter().choose(&mut rand::rng()).copied()
+        Ok(res.into_iter())
     }
 
-    /// Split the list into N chunks, then grab a random course from each chunk.
+    /// Split the pool into N chunks, then grab a random course from each chunk. If the size of the
+    /// pool is not divisible by N, the first chunks each receive one extra course.
     ///
     /// # Errors
-    /// - If the current number of active courses cannot be evenly divded by the given number of
-    ///   chunks.
+    /// - If N is 0 or greater than the size of the pool.
     pub fn get_random_by_chunks(
         &self,
         num_chunks: usize,
-    ) -> Result<impl Iterator<Item = usize>, ()> {
-        let curr_vec: Vec<usize> = self.current.iter().copied().collect();
-        let len = self.current.len();
+    ) -> Result<impl Iterator<Item = CourseId>, ()> {
+        Ok(self
+            .get_random_by_tiers(&TierStrategy::Even(num_chunks))?
+            .map(|(_, course)| course))
+    }
+
+    /// Start a new prix from a random course in each tier of the given strategy, replacing any
+    /// prix already in progress. Starting the prix is recorded in history, so it can be undone.
+    ///
+    /// # Errors
+    /// - If the strategy cannot be applied to the active courses. See `TierStrategy::split`.
+    pub fn start_prix(&mut self, strategy: &TierStrategy) -> Result<(), ()> {
+        let remaining = self
+            .get_random_by_tiers(strategy)?
+            .map(|(tier, course)| PrixEntry {
+                label: tier.label,
+                course,
+            })
+            .collect();
+
+        self.begin_prix(remaining);
+        Ok(())
+    }
 
-        if !len.is_multiple_of(num_chunks) {
+    fn begin_prix(&mut self, remaining: Vec<PrixEntry>) {
+        // Prix numbers are never reused, even if starting a prix is undone, so results recorded
+        // for the undone prix are not mixed up with the next one
+        let number = self.prix_count + 1;
+        let action = Action::PrixStart {
+            prix: Prix {
+                number,
+                remaining,
+                raced: Vec::new(),
+            },
+            replaced: self.prix.clone(),
+        };
+        self.apply_action(&action);
+        self.history.push(action);
+        self.emit(&Event::PrixStarted { number });
+    }
+
+    /// Pick a random course that has not yet been raced in the current prix, and remove it from
+    /// the list. The roll is recorded in history, and undoing it makes the course remaining in
+    /// the prix again. Only courses rolled this way count as raced in the prix: courses added or
+    /// removed by hand do not affect it.
+    ///
+    /// Returns `None` if there is no prix in progress, or if it is finished.
+    pub fn roll_prix(&mut self) -> Option<PrixEntry> {
+        let entry = self
+            .prix
+            .as_ref()?
+            .remaining
+            .choose(&mut rand::rng())?
+            .clone();
+
+        let action = Action::PrixRoll(entry.course);
+        self.apply_action(&action);
+        self.history.push(action);
+        self.emit(&Event::PrixRolled {
+            label: entry.label.clone(),
+            course: entry.course,
+        });
+        Some(entry)
+    }
+
+    /// End the current prix. Courses raced so far stay removed, and the rest stay active. Ending
+    /// the prix is recorded in history, so it can be undone.
+    pub fn end_prix(&mut self) {
+        if let Some(prix) = self.prix.clone() {
+            let action = Action::PrixEnd(prix);
+            self.apply_action(&action);
+            self.history.push(action);
+            self.emit(&Event::PrixEnded);
+        }
+    }
+
+    /// Get a view of the current prix, if any.
+    #[inline]
+    pub fn get_prix(&self) -> Option<&Prix> {
+        self.prix.as_ref()
+    }
+
+    /// Start ranking every course through pairwise comparisons, replacing any ranking already in
+    /// progress. Use `next_comparison` and `answer_comparison` to drive the ranking.
+    ///
+    /// If `player` is given, the finished ranking becomes that player's personal ranking.
+    /// Otherwise, it is written directly into the course ranks. If there are fewer than two
+    /// courses to rank, the ranking is finished and stored right away.
+    ///
+    /// # Errors
+    /// - If `player` is given, but no player has that name.
+    pub fn start_ranking(&mut self, player: Option<&str>) -> Result<(), ()> {
+        // The session keeps the player's name as stored, however it was typed
+        let player = match player {
+            Some(name) => Some(self.players[self.find_player(name).ok_or(())?].name.clone()),
+            None => None,
+        };
+
+        self.ranking = Some(RankingSession::new(self.get_in_game(), player));
+        // With fewer than two courses there is nothing to compare, so the ranking is already done
+        let event = self.finish_ranking();
+        self.emit(&event);
+        Ok(())
+    }
+
+    /// Get the name of the player whose ranking is in progress. Returns `None` if there is no
+    /// ranking in progress, or if it is not on behalf of a player.
+    pub fn ranking_player(&self) -> Option<&str> {
+        self.ranking.as_ref()?.player.as_deref()
+    }
+
+    /// Get the next pair of courses to compare for the ranking in progress, if any.
+    pub fn next_comparison(&self) -> Option<(CourseId, CourseId)> {
+        self.ranking.as_ref()?.next_comparison()
+    }
+
+    /// Answer the comparison returned by `next_comparison`. `first_is_better` should be true if
+    /// the first course of the pair is better than the second. Once every course has been ranked,
+    /// the ranking is stored (see `start_ranking`) and the ranking session ends.
+    ///
+    /// # Errors
+    /// - If there is no ranking in progress.
+    pub fn answer_comparison(&mut self, first_is_better: bool) -> Result<(), ()> {
+        self.ranking.as_mut().ok_or(())?.answer(first_is_better);
+        let event = self.finish_ranking();
+        self.emit(&event);
+        Ok(())
+    }
+
+    // If the ranking in progress is finished, store it and end the session. Returns the one event
+    // to emit for the change: the ranking stored, or else the session moving on
+    fn finish_ranking(&mut self) -> Event {
+        let Some(session) = self.ranking.as_mut() else {
+            return Event::RankingUpdated;
+        };
+        let Some(order) = session.ranking().map(<[CourseId]>::to_vec) else {
+            return Event::RankingUpdated;
+        };
+
+        let event = if let Some(name) = session.player.take() {
+            if let Some(player_i) = self.find_player(&name) {
+                self.players[player_i].ranking = order;
+            }
+            Event::PlayerUpdated { name }
+        } else {
+            self.write_ranks(&order);
+            Event::RanksChanged
+        };
+        self.ranking = None;
+        event
+    }
+
+    /// Get the progress of the ranking in progress, if any, as the number of ranked courses and
+    /// the total number of courses.
+    pub fn ranking_progress(&self) -> Option<(usize, usize)> {
+        self.ranking.as_ref().map(RankingSession::progress)
+    }
+
+    /// Discard the ranking in progress, leaving ranks untouched.
+    pub fn end_ranking(&mut self) {
+        if self.ranking.take().is_some() {
+            self.emit(&Event::RankingUpdated);
+        }
+    }
+
+    /// Rewrite course ranks from a full ordering of course ids, best first. Ids of courses not
+    /// in the list are skipped.
+    pub fn apply_ranking(&mut self, order: &[CourseId]) {
+        self.write_ranks(order);
+        self.emit(&Event::RanksChanged);
+    }
+
+    fn write_ranks(&mut self, order: &[CourseId]) {
+        let ranks: BTreeMap<CourseId, usize> = order
+            .iter()
+            .enumerate()
+            .map(|(pos, &id)| (id, pos + 1))
+            .collect();
+        for course in &mut self.courses {
+            if let Some(&rank) = ranks.get(&course.id) {
+                course.rank = rank;
+            }
+        }
+    }
+
+    /// Add a new player with the given name.
+    ///
+    /// # Errors
+    /// - If a player with that name already exists.
+    pub fn add_player(&mut self, name: &str) -> Result<(), ()> {
+        if self.find_player(name).is_some() {
             return Err(());
         }
-        let chunk_size = len / num_chunks;
 
-        let mut rng = rand::rng();
-        let mut res: Vec<usize> = Vec::with_capacity(num_chunks);
+        self.players.push(Player::new(name));
+        self.emit(&Event::PlayerAdded {
+            name: name.to_owned(),
+        });
+        Ok(())
+    }
+
+    /// Find a player's index by name. `name` is case-insensitive.
+    pub fn find_player(&self, name: &str) -> Option<usize> {
+        self.players
+            .iter()
+            .position(|p| p.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Get a view of all players.
+    #[inline]
+    pub fn get_players(&self) -> &[Player] {
+        &self.players
+    }
+
+    /// Set whether a player is playing in the current session.
+    ///
+    /// # Panics
+    /// - If `player_i` is out of bounds.
+    pub fn set_present(&mut self, player_i: usize, present: bool) {
+        self.players[player_i].present = present;
+        self.emit(&Event::PlayerUpdated {
+            name: self.players[player_i].name.clone(),
+        });
+    }
+
+    /// Get a view of the players in the current session.
+    pub fn present_players(&self) -> impl Iterator<Item = &Player> {
+        self.players.iter().filter(|p| p.present)
+    }
+
+    /// Ban a course for a player, so it is left out of the pool while they are present.
+    ///
+    /// # Panics
+    /// - If `player_i` is out of bounds.
+    pub fn ban(&mut self, player_i: usize, course: CourseId) {
+        self.players[player_i].banned.insert(course);
+        self.emit(&Event::PlayerUpdated {
+            name: self.players[player_i].name.clone(),
+        });
+    }
 
-        for chunk in curr_vec.chunks_exact(chunk_size) {
-            // We already validated the chunks, so unwrap() is fine here
-            #[allow(clippy::missing_panics_doc)]
-            let selection = *chunk.choose(&mut rng).unwrap();
-            res.push(selection);
+    /// Lift a player's ban on a course.
+    ///
+    /// # Panics
+    /// - If `player_i` is out of bounds.
+    pub fn unban(&mut self, player_i: usize, course: CourseId) {
+        self.players[player_i].banned.remove(&course);
+        self.emit(&Event::PlayerUpdated {
+            name: self.players[player_i].name.clone(),
+        });
+    }
+
+    /// Combine every player's personal ranking into the shared course ranks. Players without a
+    /// ranking are ignored.
+    ///
+    /// # Errors
+    /// - If no player has a ranking.
+    pub fn aggregate_rankings(&mut self, method: AggregationMethod) -> Result<(), ()> {
+        let rankings = self.player_rankings();
+        if rankings.is_empty() {
+            return Err(());
         }
 
-        Ok(res.into_iter())
+        let courses: Vec<CourseId> = self.courses.iter().map(|c| c.id).collect();
+        let order = aggregate::aggregate(&rankings, &courses, method);
+        self.apply_ranking(&order);
+        Ok(())
+    }
+
+    /// Rate how much the players' personal rankings disagree about each course, most contested
+    /// first.
+    pub fn contested_courses(&self) -> Vec<Contested> {
+        aggregate::contested(&self.player_rankings())
+    }
+
+    fn player_rankings(&self) -> Vec<&[CourseId]> {
+        self.players
+            .iter()
+            .map(|p| p.ranking.as_slice())
+            .filter(|r| !r.is_empty())
+            .collect()
+    }
+
+    /// Start a veto phase, replacing any veto phase already in progress. `num_candidates` random
+    /// courses are drawn from the pool, then each present player, in roster order, strikes one
+    /// with `strike`. Once every player has struck a course, `race_survivors` starts a prix with
+    /// the remaining candidates. Starting the veto phase is recorded in history, so it can be
+    /// undone.
+    ///
+    /// # Errors
+    /// - If no players are present.
+    /// - If `num_candidates` is not greater than the number of present players.
+    /// - If the pool has fewer than `num_candidates` courses.
+    pub fn start_veto(&mut self, num_candidates: usize) -> Result<(), ()> {
+        let order: Vec<usize> = (0..self.players.len())
+            .filter(|&i| self.players[i].present)
+            .collect();
+        if order.is_empty() || num_candidates <= order.len() {
+            return Err(());
+        }
+
+        let candidates = self
+            .get_pool()
+            .choose_multiple(&mut rand::rng(), num_candidates);
+        if candidates.len() < num_candidates {
+            return Err(());
+        }
+
+        let action = Action::VetoStart {
+            veto: VetoPhase {
+                candidates,
+                strikes: Vec::new(),
+                order,
+            },
+            replaced: self.veto.clone(),
+        };
+        self.apply_action(&action);
+        self.history.push(action);
+        self.emit(&Event::VetoStarted);
+        Ok(())
+    }
+
+    /// Strike a candidate on behalf of the player whose turn it is. The strike is recorded in
+    /// history, so it can be undone.
+    ///
+    /// # Errors
+    /// - If there is no veto phase in progress, or every player has already struck a course.
+    /// - If the course is not a candidate.
+    pub fn strike(&mut self, course: CourseId) -> Result<(), ()> {
+        let veto = self.veto.as_ref().ok_or(())?;
+        let player = veto.striker().ok_or(())?;
+        if !veto.candidates.contains(&course) {
+            return Err(());
+        }
+
+        let action = Action::Veto { player, course };
+        self.apply_action(&action);
+        self.history.push(action);
+        self.emit(&Event::CourseStruck {
+            player: self.players[player].name.clone(),
+            course,
+        });
+        Ok(())
+    }
+
+    /// Race the candidates that survived the veto phase, as a prix. See `start_prix`. Ending the
+    /// veto phase and starting the prix are recorded in history as one action, so undoing it
+    /// brings back the veto phase and the prix it replaced.
+    ///
+    /// # Errors
+    /// - If there is no veto phase in progress, or not every player has struck a course.
+    pub fn race_survivors(&mut self) -> Result<(), ()> {
+        let veto = self.veto.clone().filter(VetoPhase::is_finished).ok_or(())?;
+
+        let number = self.prix_count + 1;
+        let remaining = veto
+            .candidates
+            .iter()
+            .map(|&course| PrixEntry {
+                label: "Survived veto".to_owned(),
+                course,
+            })
+            .collect();
+        let action = Action::RaceSurvivors {
+            veto,
+            prix: Prix {
+                number,
+                remaining,
+                raced: Vec::new(),
+            },
+            replaced: self.prix.clone(),
+        };
+        self.apply_action(&action);
+        self.history.push(action);
+        self.emit(&Event::VetoEnded);
+        self.emit(&Event::PrixStarted { number });
+        Ok(())
+    }
+
+    /// Discard the veto phase in progress. Discarding it is recorded in history, so it can be
+    /// undone, and strikes stay in history.
+    pub fn end_veto(&mut self) {
+        if let Some(veto) = self.veto.clone() {
+            let action = Action::VetoEnd(veto);
+            self.apply_action(&action);
+            self.history.push(action);
+            self.emit(&Event::VetoEnded);
+        }
+    }
+
+    /// Get a view of the veto phase in progress, if any.
+    #[inline]
+    pub fn get_veto(&self) -> Option<&VetoPhase> {
+        self.veto.as_ref()
+    }
+
+    /// Get the most recently rolled or removed course that has not been undone.
+    pub fn last_removed(&self) -> Option<CourseId> {
+        self.recent_removed().next()
+    }
+
+    /// Get the rolled or removed courses that have not been undone, most recent first. Courses
+    /// that were deleted since are left out.
+    pub fn recent_removed(&self) -> impl Iterator<Item = CourseId> {
+        self.history
+            .recent_removed()
+            .filter(|&id| self.get_course(id).is_some())
+    }
+
+    /// Record the result of a race on a course. `placements` maps player names, in any case, to
+    /// placements, starting at 1. The result is stored under the names as the players have them.
+    /// If the course was last raced in a prix, even one that has ended since, the result counts
+    /// towards that prix.
+    ///
+    /// # Errors
+    /// - If `placements` is empty, or names a player who does not exist, or names a player twice.
+    /// - If a placement is 0, or two players share a placement.
+    pub fn record_result(
+        &mut self,
+        course: CourseId,
+        placements: BTreeMap<String, u8>,
+    ) -> Result<(), ()> {
+        let placements = self.canonical_placements(placements).ok_or(())?;
+        let prix = self.prix_raced_in(course);
+
+        self.results.push(RaceResult {
+            course,
+            prix,
+            tournament_match: None,
+            placements,
+        });
+        self.emit(&Event::ResultRecorded { course });
+        Ok(())
+    }
+
+    // Check placements and key them by each player's name as stored, however it was typed, so a
+    // player's results are never split between spellings. `None` if they are invalid
+    fn canonical_placements(
+        &self,
+        placements: BTreeMap<String, u8>,
+    ) -> Option<BTreeMap<String, u8>> {
+        let mut seen = BTreeSet::new();
+        let mut canonical = BTreeMap::new();
+        for (name, placement) in placements {
+            let player = &self.players[self.find_player(&name)?];
+            if placement == 0
+                || !seen.insert(placement)
+                || canonical.insert(player.name.clone(), placement).is_some()
+            {
+                return None;
+            }
+        }
+        (!canonical.is_empty()).then_some(canonical)
+    }
+
+    // Number of the prix the course was last removed in, if its latest removal that has not been
+    // undone was a prix roll. The prix may have ended since
+    fn prix_raced_in(&self, course: CourseId) -> Option<usize> {
+        let mut past = self.history.past.iter().rev();
+        past.find(|a| match a {
+            Action::Remove(id) | Action::Roll(id) | Action::PrixRoll(id) => *id == course,
+            _ => false,
+        })
+        .filter(|a| matches!(a, Action::PrixRoll(_)))?;
+
+        // Courses are only rolled from the prix in progress, so it is the latest one started
+        past.find_map(|a| match a {
+            Action::PrixStart { prix, .. } | Action::RaceSurvivors { prix, .. } => {
+                Some(prix.number)
+            }
+            _ => None,
+        })
+    }
+
+    /// Get a view of every recorded race result, oldest first.
+    #[inline]
+    pub fn get_results(&self) -> &[RaceResult] {
+        &self.results
+    }
+
+    /// Replace the placements of a recorded race result. See `record_result`.
+    ///
+    /// # Errors
+    /// - If `result_i` is out of bounds.
+    /// - If the placements are invalid. See `record_result`.
+    pub fn edit_result(
+        &mut self,
+        result_i: usize,
+        placements: BTreeMap<String, u8>,
+    ) -> Result<(), ()> {
+        if result_i >= self.results.len() {
+            return Err(());
+        }
+        let placements = self.canonical_placements(placements).ok_or(())?;
+
+        let result = &mut self.results[result_i];
+        result.placements = placements;
+        let course = result.course;
+        self.emit(&Event::ResultEdited { course });
+        Ok(())
+    }
+
+    /// Delete a recorded race result.
+    ///
+    /// # Errors
+    /// - If `result_i` is out of bounds.
+    pub fn remove_result(&mut self, result_i: usize) -> Result<RaceResult, ()> {
+        if result_i >= self.results.len() {
+            return Err(());
+        }
+
+        let result = self.results.remove(result_i);
+        self.emit(&Event::ResultRemoved {
+            course: result.course,
+        });
+        Ok(result)
+    }
+
+    /// Set the points awarded for each placement.
+    pub fn set_point_table(&mut self, table: PointTable) {
+        self.point_table = table;
+        self.emit(&Event::PointTableChanged);
+    }
+
+    /// Get the points awarded for each placement.
+    #[inline]
+    pub fn get_point_table(&self) -> &PointTable {
+        &self.point_table
+    }
+
+    /// Get the standings over every recorded result, best first.
+    pub fn season_standings(&self) -> Vec<Standing> {
+        results::standings(&self.results, &self.point_table)
+    }
+
+    /// Get the standings over the results of one prix, best first.
+    pub fn prix_standings(&self, prix_number: usize) -> Vec<Standing> {
+        let results = self.results.iter().filter(|r| r.prix == Some(prix_number));
+        results::standings(results, &self.point_table)
+    }
+
+    /// Compute player ratings from every recorded result. See `Ratings::compute`.
+    pub fn ratings(&self) -> Ratings {
+        Ratings::compute(&self.results, &RatingConfig::default())
+    }
+
+    /// Get the player with the best average placement on each course that has results.
+    pub fn course_leaders(&self) -> Vec<CourseLeader> {
+        results::course_leaders(&self.results)
+    }
+
+    /// Start a tournament, replacing any tournament already in progress. Use
+    /// `next_tournament_round` to generate its first round.
+    ///
+    /// # Errors
+    /// - If the tournament cannot be created. See `Tournament::new`.
+    pub fn start_tournament(
+        &mut self,
+        format: Format,
+        participants: Vec<Participant>,
+        courses_per_match: usize,
+    ) -> Result<(), TournamentError> {
+        let mut tournament = Tournament::new(format, participants, courses_per_match)?;
+        self.tournament_count += 1;
+        tournament.number = self.tournament_count;
+        self.tournament = Some(tournament);
+        self.emit(&Event::TournamentUpdated);
+        Ok(())
+    }
+
+    /// Generate the next round of the tournament in progress. Each match is assigned courses that
+    /// have not been used earlier in the tournament, regardless of whether they are active.
+    ///
+    /// # Errors
+    /// - If there is no tournament in progress, or it is finished.
+    /// - If the round cannot be generated. See `Tournament::next_round`.
+    pub fn next_tournament_round(&mut self) -> Result<&[Match], TournamentError> {
+        let courses = self.get_in_game();
+        let tournament = self.tournament.as_mut().ok_or(TournamentError::Finished)?;
+        tournament.next_round(courses)?;
+        self.emit(&Event::TournamentUpdated);
+
+        Ok(self
+            .tournament
+            .as_ref()
+            .and_then(|t| t.rounds.last())
+            .map(Vec::as_slice)
+            .unwrap_or_default())
+    }
+
+    /// Record the result of a race on a course of a match in the tournament in progress. Both
+    /// indices start at 0. The result counts towards the match when its winner is worked out. See
+    /// `record_result`.
+    ///
+    /// # Errors
+    /// - If there is no tournament in progress.
+    /// - If the match does not exist or is a bye, or a later round has been generated.
+    /// - If the course is not assigned to the match.
+    /// - If the placements are invalid. See `record_result`.
+    pub fn record_match_result(
+        &mut self,
+        round_i: usize,
+        match_i: usize,
+        course: CourseId,
+        placements: BTreeMap<String, u8>,
+    ) -> Result<(), TournamentError> {
+        let tournament = self
+            .tournament
+            .as_ref()
+            .ok_or(TournamentError::NoSuchMatch)?;
+        let m = tournament.check_open(round_i, match_i)?;
+        if !m.courses.contains(&course) {
+            return Err(TournamentError::CourseNotInMatch);
+        }
+        let tournament_match = Some(tournament.match_ref(round_i, match_i));
+        let placements = self
+            .canonical_placements(placements)
+            .ok_or(TournamentError::InvalidResult)?;
+
+        self.results.push(RaceResult {
+            course,
+            prix: None,
+            tournament_match,
+            placements,
+        });
+        self.emit(&Event::ResultRecorded { course });
+        Ok(())
+    }
+
+    /// Record the winner of a match in the tournament in progress. If `winner` is `None`, the
+    /// winner is worked out from the race results recorded for the match. See
+    /// `Tournament::winner_from_results`. Returns the name of the winner.
+    ///
+    /// # Errors
+    /// - If there is no tournament in progress.
+    /// - If the match does not exist or is a bye, or a later round has been generated.
+    /// - If `winner` is given but is not in the match, or is not given and cannot be worked out.
+    pub fn record_match_winner(
+        &mut self,
+        round_i: usize,
+        match_i: usize,
+        winner: Option<&str>,
+    ) -> Result<String, TournamentError> {
+        let tournament = self
+            .tournament
+            .as_mut()
+            .ok_or(TournamentError::NoSuchMatch)?;
+
+        let winner = if let Some(name) = winner {
+            name.to_owned()
+        } else {
+            tournament.check_open(round_i, match_i)?;
+            tournament
+                .winner_from_results(round_i, match_i, &self.results, &self.point_table)
+                .ok_or(TournamentError::Undecided)?
+        };
+
+        tournament.record_winner(round_i, match_i, &winner)?;
+        self.emit(&Event::TournamentUpdated);
+        Ok(winner)
+    }
+
+    /// End the tournament in progress.
+    pub fn end_tournament(&mut self) {
+        if self.tournament.take().is_some() {
+            self.emit(&Event::TournamentUpdated);
+        }
+    }
+
+    /// Get a view of the tournament in progress, if any.
+    #[inline]
+    pub fn get_tournament(&self) -> Option<&Tournament> {
+        self.tournament.as_ref()
     }
 
-    /// Make all courses active and clear all history.
+    /// Make all courses active, end the current prix and veto phase, and clear all history. The
+    /// race log is kept. See `get_removed_in_order`.
     pub fn reset(&mut self) {
         self.current.extend(self.get_removed());
+        self.prix = None;
+        self.veto = None;
         self.history.reset();
+        self.emit(&Event::Reset);
     }
 
     /// Get a view of the action history.
@@ -162,18 +1777,46 @@ impl CourseList {
         &self.history
     }
 
+    /// Get the sessions the list was used in, oldest first.
+    #[inline]
+    pub fn get_sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// Get the courses removed from the list, by rolling or by hand, in the order they were
+    /// removed, including those removed before the list was last reset. Removals that were
+    /// undone, and courses that were deleted since, are left out.
+    pub fn get_removed_in_order(&self) -> Vec<CourseId> {
+        self.race_log
+            .iter()
+            .copied()
+            .filter(|&id| self.get_course(id).is_some())
+            .collect()
+    }
+
     /// Get a view of active courses.
     #[inline]
-    pub fn get_current(&self) -> impl Iterator<Item = usize> {
+    pub fn get_current(&self) -> impl Iterator<Item = CourseId> {
         self.current.iter().copied()
     }
 
-    /// Get a view of removed courses.
-    pub fn get_removed(&self) -> impl Iterator<Item = usize> + use<> {
+    /// Get a view of removed courses. Retired courses are not included.
+    pub fn get_removed(&self) -> impl Iterator<Item = CourseId> + use<> {
         let current = self.current.clone();
 
-        (0..self.courses.len())
-            .filter(move |x| !current.contains(x))
+        self.get_in_game().filter(move |x| !current.contains(x))
+    }
+
+    /// Get a view of every course that is not retired, active or not.
+    pub fn get_in_game(&self) -> impl Iterator<Item = CourseId> + use<> {
+        let in_game: Vec<CourseId> = self
+            .courses
+            .iter()
+            .map(|c| c.id)
+            .filter(|id| !self.retired.contains(id))
+            .collect();
+
+        in_game.into_iter()
     }
 
     /// Undo the most recent action.
@@ -182,7 +1825,8 @@ impl CourseList {
     /// - If there are no actions to roll back.
     pub fn roll_back(&mut self) -> Result<(), ()> {
         let action: Action = self.history.back().ok_or(())?;
-        self.undo_action(action);
+        self.undo_action(&action);
+        self.emit(&Event::Undo);
         Ok(())
     }
 
@@ -192,21 +1836,133 @@ impl CourseList {
     /// - If there are no undone actions.
     pub fn roll_forward(&mut self) -> Result<(), ()> {
         let action: Action = self.history.forward().ok_or(())?;
-        self.apply_action(action);
+        self.apply_action(&action);
+        self.emit(&Event::Redo);
         Ok(())
     }
 
-    fn apply_action(&mut self, action: Action) {
+    fn apply_action(&mut self, action: &Action) {
         match action {
-            Action::Add(i) => self.inner_add(i),
-            Action::Remove(i) => self.inner_remove(i),
+            &Action::Add(id) => self.inner_add(id),
+            &Action::Remove(id) | &Action::Roll(id) => {
+                self.inner_remove(id);
+                self.race_log.push(id);
+            }
+            Action::PrixStart { prix, .. } => {
+                self.prix_count = self.prix_count.max(prix.number);
+                self.prix = Some(prix.clone());
+            }
+            &Action::PrixRoll(id) => {
+                self.inner_remove(id);
+                self.race_log.push(id);
+                if let Some(prix) = &mut self.prix {
+                    prix.mark_raced(id);
+                }
+            }
+            Action::PrixEnd(_) => self.prix = None,
+            Action::VetoStart { veto, .. } => self.veto = Some(veto.clone()),
+            &Action::Veto { player, course } => {
+                if let Some(veto) = &mut self.veto {
+                    veto.apply_strike(player, course);
+                }
+            }
+            Action::VetoEnd(_) => self.veto = None,
+            Action::RaceSurvivors { prix, .. } => {
+                self.veto = None;
+                self.prix_count = self.prix_count.max(prix.number);
+                self.prix = Some(prix.clone());
+            }
+            Action::Create { course, unlinked } => {
+                let position = self
+                    .courses
+                    .iter()
+                    .position(|c| c.rank >= course.rank)
+                    .unwrap_or(self.courses.len());
+                self.insert_course(course.clone(), position, true);
+                self.unlink(unlinked.as_ref());
+            }
+            Action::Edit {
+                after, unlinked, ..
+            } => {
+                self.replace_course(after);
+                self.unlink(unlinked.as_ref());
+            }
+            Action::Delete {
+                course, unlinked, ..
+            } => {
+                self.take_course(course.id);
+                self.unlink(unlinked.as_ref());
+            }
         }
     }
 
-    fn undo_action(&mut self, action: Action) {
+    fn undo_action(&mut self, action: &Action) {
         match action {
-            Action::Add(i) => self.inner_remove(i),
-            Action::Remove(i) => self.inner_add(i),
+            &Action::Add(id) => self.inner_remove(id),
+            &Action::Remove(id) | &Action::Roll(id) => {
+                self.inner_add(id);
+                self.unlog_race(id);
+            }
+            Action::PrixStart { replaced, .. } => self.prix.clone_from(replaced),
+            &Action::PrixRoll(id) => {
+                self.inner_add(id);
+                self.unlog_race(id);
+                if let Some(prix) = &mut self.prix {
+                    prix.unmark_raced(id);
+                }
+            }
+            Action::PrixEnd(prix) => self.prix = Some(prix.clone()),
+            Action::VetoStart { replaced, .. } => self.veto.clone_from(replaced),
+            &Action::Veto { course, .. } => {
+                if let Some(veto) = &mut self.veto {
+                    veto.undo_strike(course);
+                }
+            }
+            Action::VetoEnd(veto) => self.veto = Some(veto.clone()),
+            Action::RaceSurvivors { veto, replaced, .. } => {
+                self.prix.clone_from(replaced);
+                self.veto = Some(veto.clone());
+            }
+            Action::Create { course, unlinked } => {
+                self.take_course(course.id);
+                self.relink(unlinked.as_ref());
+            }
+            Action::Edit {
+                before, unlinked, ..
+            } => {
+                self.replace_course(before);
+                self.relink(unlinked.as_ref());
+            }
+            Action::Delete {
+                course,
+                position,
+                active,
+                unlinked,
+            } => {
+                self.insert_course(course.clone(), *position, *active);
+                self.relink(unlinked.as_ref());
+            }
+        }
+    }
+
+    // Unlink the list from its game again when redoing an action that unlinked it
+    fn unlink(&mut self, unlinked: Option<&GameRef>) {
+        if unlinked.is_some() {
+            self.game = None;
+        }
+    }
+
+    // Link the list back to the game an undone action unlinked it from
+    fn relink(&mut self, unlinked: Option<&GameRef>) {
+        if let Some(game) = unlinked {
+            self.game = Some(game.clone());
+        }
+    }
+
+    // Take back the latest race log entry of an undone removal
+    fn unlog_race(&mut self, course: CourseId) {
+        if let Some(i) = self.race_log.iter().rposition(|&id| id == course) {
+            self.race_log.remove(i);
         }
     }
 
@@ -215,3 +1971,484 @@ impl CourseList {
         SAVES_DIR.join(&self.save_name)
     }
 }
+
+impl Index<CourseId> for CourseList {
+    type Output = Course;
+
+    /// Get the course with the given id.
+    ///
+    /// # Panics
+    /// - If there is no course with that id.
+    fn index(&self, id: CourseId) -> &Course {
+        self.get_course(id)
+            .unwrap_or_else(|| panic!("There is no course with id {id}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+
+    // A list of `count` courses ranked 1 to `count`, with ids 0 to `count - 1`, all active
+    fn list_of(count: u8) -> CourseList {
+        let courses = (0..count)
+            .map(|n| {
+                let coord = Coord::new(1 + n / 24, 1 + n / 4 % 6, 1 + n % 4);
+                let name = format!("Course {n}");
+                Course::new(
+                    CourseId(n.into()),
+                    usize::from(n) + 1,
+                    coord,
+                    Origin::MK8,
+                    &name,
+                )
+            })
+            .collect();
+        CourseList::from_courses("test.json", courses)
+    }
+
+    fn reload(list: &CourseList) -> CourseList {
+        serde_json::from_str(&serde_json::to_string(list).unwrap()).unwrap()
+    }
+
+    // Record the events the list emits
+    fn record_events(list: &mut CourseList) -> Rc<RefCell<Vec<Event>>> {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let recorded = Rc::clone(&events);
+        list.subscribe(move |change: &Change| recorded.borrow_mut().push(change.event.clone()));
+        events
+    }
+
+    #[test]
+    fn finished_rankings_are_stored_with_one_event() {
+        let mut list = list_of(3);
+        list.add_player("Alice").unwrap();
+        let events = record_events(&mut list);
+
+        // Rank the courses in reverse, for Alice
+        list.start_ranking(Some("alice")).unwrap();
+        while let Some((a, b)) = list.next_comparison() {
+            list.answer_comparison(a > b).unwrap();
+        }
+        assert_eq!(list.get_players()[0].ranking, [2, 1, 0].map(CourseId));
+        assert!(list.ranking_progress().is_none());
+        let events = events.take();
+        let (stored, answered) = events.split_last().unwrap();
+        assert_eq!(
+            *stored,
+            Event::PlayerUpdated {
+                name: "Alice".to_owned()
+            }
+        );
+        assert!(answered.iter().all(|e| *e == Event::RankingUpdated));
+
+        // With one course, the list's own ranking is stored as soon as it starts
+        let mut list = list_of(1);
+        let events = record_events(&mut list);
+        list.start_ranking(None).unwrap();
+        assert!(list.ranking_progress().is_none());
+        assert_eq!(events.take(), [Event::RanksChanged]);
+    }
+
+    #[test]
+    fn prix_survives_saving_and_undo() {
+        let mut list = list_of(6);
+        list.start_prix(&TierStrategy::Even(3)).unwrap();
+        let entry = list.roll_prix().unwrap();
+        assert!(!list.get_current().any(|id| id == entry.course));
+
+        let mut list = reload(&list);
+        let prix = list.get_prix().unwrap();
+        assert_eq!((prix.number, prix.remaining.len()), (1, 2));
+        assert_eq!(prix.raced, std::slice::from_ref(&entry));
+
+        list.roll_back().unwrap();
+        assert!(list.get_current().any(|id| id == entry.course));
+        assert_eq!(list.get_prix().unwrap().remaining.len(), 3);
+        list.roll_forward().unwrap();
+        assert_eq!(list.get_prix().unwrap().raced, [entry]);
+
+        list.end_prix();
+        assert!(list.get_prix().is_none());
+        list.roll_back().unwrap();
+        assert_eq!(list.get_prix().unwrap().raced.len(), 1);
+    }
+
+    #[test]
+    fn veto_survivors_are_raced_as_a_prix() {
+        let mut list = list_of(8);
+        for name in ["Alice", "Bob", "Carol"] {
+            list.add_player(name).unwrap();
+        }
+        list.set_present(1, false);
+        assert!(
+            list.start_veto(2).is_err(),
+            "there must be more candidates than strikers"
+        );
+        list.start_veto(4).unwrap();
+
+        let veto = list.get_veto().unwrap().clone();
+        assert_eq!(veto.order, [0, 2]);
+        assert!(list.strike(CourseId(99)).is_err());
+        assert!(
+            list.race_survivors().is_err(),
+            "every player must strike first"
+        );
+        list.strike(veto.candidates[0]).unwrap();
+        list.strike(veto.candidates[1]).unwrap();
+        assert!(list.strike(veto.candidates[2]).is_err());
+
+        list.race_survivors().unwrap();
+        assert!(list.get_veto().is_none());
+        let prix = list.get_prix().unwrap();
+        let raced: Vec<CourseId> = prix.remaining.iter().map(|e| e.course).collect();
+        assert_eq!(raced, veto.candidates[2..]);
+
+        // Undoing brings back the finished veto phase, then each strike in turn
+        list.roll_back().unwrap();
+        assert!(list.get_prix().is_none());
+        assert!(list.get_veto().unwrap().is_finished());
+        list.roll_back().unwrap();
+        assert_eq!(list.get_veto().unwrap().striker(), Some(2));
+
+        let mut list = reload(&list);
+        list.end_veto();
+        assert!(list.get_veto().is_none());
+        list.roll_back().unwrap();
+        assert_eq!(list.get_veto().unwrap().strikes.len(), 1);
+    }
+
+    #[test]
+    fn results_use_the_players_names_as_stored() {
+        let mut list = list_of(2);
+        list.add_player("Alice").unwrap();
+        list.add_player("Bob").unwrap();
+        let placements = |entries: &[(&str, u8)]| {
+            entries
+                .iter()
+                .map(|&(name, placement)| (name.to_owned(), placement))
+                .collect::<BTreeMap<String, u8>>()
+        };
+
+        list.record_result(CourseId(0), placements(&[("alice", 1), ("BOB", 2)]))
+            .unwrap();
+        list.record_result(CourseId(1), placements(&[("Alice", 2), ("bob", 1)]))
+            .unwrap();
+        let names: Vec<&String> = list.get_results()[0].placements.keys().collect();
+        assert_eq!(names, ["Alice", "Bob"]);
+
+        let standings = list.season_standings();
+        assert_eq!(standings.len(), 2);
+        assert_eq!((standings[0].points, standings[0].races), (27, 2));
+
+        let twice = placements(&[("alice", 1), ("Alice", 2)]);
+        assert!(list.record_result(CourseId(0), twice).is_err());
+        assert!(
+            list.record_result(CourseId(0), placements(&[("Carol", 1)]))
+                .is_err()
+        );
+        assert!(
+            list.record_result(CourseId(0), placements(&[("Bob", 0)]))
+                .is_err()
+        );
+        assert!(list.record_result(CourseId(0), placements(&[])).is_err());
+
+        list.edit_result(0, placements(&[("bob", 1)])).unwrap();
+        let names: Vec<&String> = list.get_results()[0].placements.keys().collect();
+        assert_eq!(names, ["Bob"]);
+    }
+
+    #[test]
+    fn changes_make_the_list_dirty_until_saved() {
+        let mut list = list_of(3);
+        assert!(!list.is_dirty());
+        assert_eq!(list.generation(), 0);
+
+        list.remove(CourseId(0));
+        list.add_player("Alice").unwrap();
+        assert!(list.is_dirty());
+        assert_eq!(list.generation(), 2);
+
+        // Failed changes change nothing
+        assert!(list.roll_forward().is_err());
+        assert!(list.add_player("alice").is_err());
+        assert_eq!(list.generation(), 2);
+
+        // As `dump_list` does, without writing the file
+        list.saved_generation.set(list.generation());
+        assert!(!list.is_dirty());
+
+        // Undoing back to the saved state is still a change
+        list.roll_back().unwrap();
+        assert!(list.is_dirty());
+        assert_eq!(list.generation(), 3);
+
+        let loaded = reload(&list);
+        assert!(!loaded.is_dirty());
+        assert_eq!(loaded.generation(), 0);
+    }
+
+    #[test]
+    fn race_log_keeps_every_removal_in_order() {
+        let mut list = list_of(4);
+        list.remove(CourseId(2));
+        list.remove(CourseId(0));
+        list.reset();
+        list.remove(CourseId(2));
+        list.remove(CourseId(3));
+        assert_eq!(
+            list.get_removed_in_order(),
+            [CourseId(2), CourseId(0), CourseId(2), CourseId(3)]
+        );
+
+        // Undone removals are taken back, and come back when redone
+        list.roll_back().unwrap();
+        assert_eq!(
+            list.get_removed_in_order(),
+            [CourseId(2), CourseId(0), CourseId(2)]
+        );
+        list.roll_forward().unwrap();
+        assert_eq!(list.get_removed_in_order().len(), 4);
+        assert_eq!(reload(&list).get_removed_in_order().len(), 4);
+
+        list.delete_course(CourseId(2)).unwrap();
+        assert_eq!(list.get_removed_in_order(), [CourseId(0), CourseId(3)]);
+
+        // Saves from before the race log start it from the history
+        let mut old = list_of(3);
+        old.remove(CourseId(1));
+        old.remove(CourseId(0));
+        old.race_log.clear();
+        old.version = 1;
+        old.migrate();
+        assert_eq!(old.get_removed_in_order(), [CourseId(1), CourseId(0)]);
+    }
+
+    // A list of `count` courses, linked to the built-in game
+    fn linked_list_of(count: u8) -> CourseList {
+        let mut list = list_of(count);
+        list.game = Some(GameDefinition::builtin().game_ref());
+        list
+    }
+
+    fn names(list: &CourseList) -> Vec<&str> {
+        let mut courses: Vec<&Course> = list.courses.iter().collect();
+        courses.sort_by_key(|c| c.rank);
+        courses.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn created_courses_unlink_until_undone() {
+        let mut list = linked_list_of(3);
+        let game = list.get_game().cloned();
+        let course = Course::new(CourseId(0), 2, Coord::new(2, 1, 1), Origin::Tour, " New ");
+        let id = list.create_course(course).unwrap();
+
+        assert_eq!(names(&list), ["Course 0", "New", "Course 1", "Course 2"]);
+        assert!(list.get_current().any(|c| c == id));
+        assert_eq!(list.get_game(), None);
+
+        list.roll_back().unwrap();
+        assert_eq!(names(&list), ["Course 0", "Course 1", "Course 2"]);
+        assert_eq!(list.get_game(), game.as_ref());
+
+        list.roll_forward().unwrap();
+        assert_eq!(names(&list), ["Course 0", "New", "Course 1", "Course 2"]);
+        assert_eq!(list.get_game(), None);
+        assert_eq!(list[id].rank, 2);
+    }
+
+    #[test]
+    fn only_data_edits_unlink() {
+        let mut list = linked_list_of(3);
+        let game = list.get_game().cloned();
+        let rerank = CourseEdit {
+            rank: Some(1),
+            ..CourseEdit::default()
+        };
+        list.edit_course(CourseId(2), rerank).unwrap();
+        assert_eq!(names(&list), ["Course 2", "Course 0", "Course 1"]);
+        assert_eq!(list.get_game(), game.as_ref());
+
+        let rename = CourseEdit {
+            name: Some("Renamed".to_owned()),
+            coord: Some(Coord::new(4, 6, 4)),
+            ..CourseEdit::default()
+        };
+        list.edit_course(CourseId(0), rename).unwrap();
+        assert_eq!(list[CourseId(0)].coord, Coord::new(4, 6, 4));
+        assert_eq!(list.get_game(), None);
+
+        list.roll_back().unwrap();
+        assert_eq!(names(&list), ["Course 2", "Course 0", "Course 1"]);
+        assert_eq!(list.get_game(), game.as_ref());
+        list.roll_back().unwrap();
+        assert_eq!(names(&list), ["Course 0", "Course 1", "Course 2"]);
+
+        list.roll_forward().unwrap();
+        list.roll_forward().unwrap();
+        assert_eq!(names(&list), ["Course 2", "Renamed", "Course 1"]);
+        assert_eq!(list.get_game(), None);
+    }
+
+    #[test]
+    fn deleted_courses_come_back_where_they_were() {
+        let mut list = linked_list_of(4);
+        let game = list.get_game().cloned();
+        list.remove(CourseId(1));
+        list.delete_course(CourseId(1)).unwrap();
+        list.delete_course(CourseId(2)).unwrap();
+        assert_eq!(names(&list), ["Course 0", "Course 3"]);
+        assert_eq!(list[CourseId(3)].rank, 2);
+        assert_eq!(list.get_game(), None);
+
+        list.roll_back().unwrap();
+        list.roll_back().unwrap();
+        assert_eq!(
+            names(&list),
+            ["Course 0", "Course 1", "Course 2", "Course 3"]
+        );
+        assert!(!list.get_current().any(|c| c == CourseId(1)));
+        assert!(list.get_current().any(|c| c == CourseId(2)));
+        assert_eq!(list.get_game(), game.as_ref());
+
+        list.roll_forward().unwrap();
+        assert_eq!(names(&list), ["Course 0", "Course 2", "Course 3"]);
+        assert_eq!(list.get_game(), None);
+    }
+
+    #[test]
+    fn invalid_course_edits() {
+        let mut list = list_of(3);
+        list.add_player("Alice").unwrap();
+        let at = |coord| Course::new(CourseId(0), 1, coord, Origin::Tour, "New");
+
+        for coord in [
+            (0, 1, 1),
+            (5, 1, 1),
+            (1, 0, 1),
+            (1, 7, 1),
+            (1, 1, 0),
+            (1, 1, 5),
+        ] {
+            let coord = Coord::new(coord.0, coord.1, coord.2);
+            assert_eq!(
+                list.create_course(at(coord)),
+                Err(EditError::InvalidCoord(coord))
+            );
+            let edit = CourseEdit {
+                coord: Some(coord),
+                ..CourseEdit::default()
+            };
+            assert_eq!(
+                list.edit_course(CourseId(0), edit),
+                Err(EditError::InvalidCoord(coord))
+            );
+        }
+
+        let taken = list.create_course(at(Coord::new(1, 1, 2)));
+        assert!(matches!(taken, Err(EditError::CoordTaken { name, .. }) if name == "Course 1"));
+        let mut blank = at(Coord::new(4, 6, 4));
+        blank.name = "  ".to_owned();
+        assert_eq!(list.create_course(blank), Err(EditError::EmptyName));
+        let mut too_low = at(Coord::new(4, 6, 4));
+        too_low.rank = 5;
+        assert_eq!(
+            list.create_course(too_low),
+            Err(EditError::RankOutOfRange(4))
+        );
+        let rerank = CourseEdit {
+            rank: Some(4),
+            ..CourseEdit::default()
+        };
+        assert_eq!(
+            list.edit_course(CourseId(0), rerank),
+            Err(EditError::RankOutOfRange(3))
+        );
+
+        let placements = [("Alice".to_owned(), 1)].into();
+        list.record_result(CourseId(0), placements).unwrap();
+        assert_eq!(list.delete_course(CourseId(0)), Err(EditError::InUse));
+        assert_eq!(
+            list.delete_course(CourseId(9)),
+            Err(EditError::NoSuchCourse)
+        );
+        assert_eq!(list.courses.len(), 3);
+        assert_eq!(list.generation(), 2);
+    }
+
+    #[test]
+    fn course_ids_are_never_reused() {
+        let new_course =
+            |name: &str| Course::new(CourseId(0), 1, Coord::new(4, 6, 4), Origin::Tour, name);
+        let mut list = list_of(3);
+        let created = list.create_course(new_course("New")).unwrap();
+        assert_eq!(created, CourseId(3));
+
+        // Also once reloaded, and once the creation is undone
+        list.delete_course(created).unwrap();
+        let mut list = reload(&list);
+        assert_eq!(
+            list.create_course(new_course("Newer")).unwrap(),
+            CourseId(4)
+        );
+        list.roll_back().unwrap();
+        assert_eq!(
+            list.create_course(new_course("Newest")).unwrap(),
+            CourseId(5)
+        );
+
+        // Saves from before the counter still avoid the ids in their race log
+        let mut old = list_of(3);
+        old.remove(CourseId(2));
+        old.courses.pop();
+        old.next_course_id = CourseId(0);
+        assert_eq!(old.create_course(new_course("New")).unwrap(), CourseId(3));
+    }
+
+    #[test]
+    fn saves_open_without_their_game() {
+        let mut list = CourseList::from_game("test.json", GameDefinition::builtin());
+        list.remove(CourseId(0));
+        let mut data = list.save_data().unwrap();
+        let stored = data.pointer("/courses/0").unwrap().as_object().unwrap();
+        assert!(stored.keys().eq(["id", "rank"]));
+
+        let loaded = CourseList::from_save_data(&data.to_string()).unwrap();
+        assert!(loaded.get_game().is_some() && loaded.get_game_error().is_none());
+
+        for (game_id, error) in [
+            ("../../../etc/passwd", "not a valid game id"),
+            ("no-such-game", "There is no game 'no-such-game'"),
+        ] {
+            *data.pointer_mut("/game/id").unwrap() = Value::from(game_id);
+            let loaded = CourseList::from_save_data(&data.to_string()).unwrap();
+            assert!(loaded.get_game_error().unwrap().contains(error));
+            assert!(loaded.get_game().is_none());
+            assert_eq!(loaded.courses.len(), list.courses.len());
+            assert_eq!(loaded[CourseId(1)].name, "Course 1");
+            assert_eq!(loaded[CourseId(1)].rank, list[CourseId(1)].rank);
+            assert!(!loaded.get_current().any(|id| id == CourseId(0)));
+        }
+    }
+
+    #[test]
+    fn prix_numbers_are_never_reused() {
+        let mut list = list_of(6);
+        list.start_prix(&TierStrategy::Even(2)).unwrap();
+        list.roll_back().unwrap();
+        assert!(list.get_prix().is_none());
+
+        let mut list = reload(&list);
+        list.start_prix(&TierStrategy::Even(2)).unwrap();
+        assert_eq!(list.get_prix().unwrap().number, 2);
+
+        // Starting over replaces the prix, and undoing that brings the replaced prix back
+        list.start_prix(&TierStrategy::Even(3)).unwrap();
+        assert_eq!(list.get_prix().unwrap().number, 3);
+        list.roll_back().unwrap();
+        assert_eq!(list.get_prix().unwrap().number, 2);
+    }
+}
@@ -0,0 +1,71 @@
+//! Module defining the `CourseEdit` struct, a set of changes to make to a course, and the
+//! `EditError` enum, for creating, editing and deleting the courses of a `CourseList`.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use super::course::{COLUMNS, CUP_SIZE, Coord, Origin, ROWS};
+
+/// Changes to make to a course with `CourseList::edit_course`. Fields left as `None` are kept as
+/// they are.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CourseEdit {
+    /// New name.
+    pub name: Option<String>,
+    /// New aliases, replacing the old ones.
+    pub aliases: Option<Vec<String>>,
+    /// New origin.
+    pub origin: Option<Origin>,
+    /// New position in the selection screen.
+    pub coord: Option<Coord>,
+    /// New rank. The courses between the old and new rank move one rank to make room.
+    pub rank: Option<usize>,
+    /// New tags, replacing the old ones.
+    pub tags: Option<Vec<String>>,
+}
+
+/// Errors that can occur while creating, editing or deleting a course.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditError {
+    /// There is no course with the given id.
+    NoSuchCourse,
+    /// The course would have no name.
+    EmptyName,
+    /// The coordinate is not on the selection screen. See `Coord::is_valid`.
+    InvalidCoord(Coord),
+    /// Another course in the game is already at the coordinate.
+    CoordTaken {
+        /// The coordinate.
+        coord: Coord,
+        /// Name of the course already there.
+        name: String,
+    },
+    /// The rank is not between 1 and the given highest rank.
+    RankOutOfRange(usize),
+    /// Something other than history refers to the course: recorded results, a player's ranking
+    /// or bans, or the prix, veto phase, ranking or tournament in progress.
+    InUse,
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchCourse => write!(f, "no such course"),
+            Self::EmptyName => write!(f, "a course needs a name"),
+            Self::InvalidCoord(coord) => write!(
+                f,
+                "invalid coordinate {coord}: row runs from 1 to {ROWS}, column from 1 to \
+                {COLUMNS}, and position from 1 to {CUP_SIZE}"
+            ),
+            Self::CoordTaken { coord, name } => write!(f, "{coord} is already taken by {name}"),
+            Self::RankOutOfRange(max) => write!(f, "the rank must be between 1 and {max}"),
+            Self::InUse => write!(
+                f,
+                "the course has results, is ranked or banned by a player, or is part of the \
+                prix, veto phase, ranking or tournament in progress"
+            ),
+        }
+    }
+}
+
+impl Error for EditError {}
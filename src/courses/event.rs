@@ -0,0 +1,111 @@
+//! Module defining the `Event` enum, describing changes made to a `CourseList`.
+
+use serde::{Deserialize, Serialize};
+
+use super::course::CourseId;
+
+/// A change made to a `CourseList`, as reported to observers. See `observer::Observer`. Courses
+/// are given by id.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type")]
+pub enum Event {
+    /// A random course was picked from the pool and removed.
+    CourseRolled {
+        /// The rolled course.
+        course: CourseId,
+    },
+    /// A course was added back into the list.
+    CourseAdded {
+        /// The added course.
+        course: CourseId,
+    },
+    /// A course was removed from the list by hand.
+    CourseRemoved {
+        /// The removed course.
+        course: CourseId,
+    },
+    /// Every course was made active and history was cleared.
+    Reset,
+    /// The most recent action was undone.
+    Undo,
+    /// The most recently undone action was redone.
+    Redo,
+    /// A new prix was started.
+    PrixStarted {
+        /// The number of the prix.
+        number: usize,
+    },
+    /// A course of the current prix was picked and removed.
+    PrixRolled {
+        /// Label of the tier the course was drawn from.
+        label: String,
+        /// The rolled course.
+        course: CourseId,
+    },
+    /// The current prix was ended.
+    PrixEnded,
+    /// A veto phase was started.
+    VetoStarted,
+    /// A course was struck in the current veto phase.
+    CourseStruck {
+        /// Name of the striking player.
+        player: String,
+        /// The struck course.
+        course: CourseId,
+    },
+    /// The current veto phase was ended.
+    VetoEnded,
+    /// A player was added.
+    PlayerAdded {
+        /// Name of the player.
+        name: String,
+    },
+    /// A player's presence, bans or ranking changed.
+    PlayerUpdated {
+        /// Name of the player.
+        name: String,
+    },
+    /// The pool mode changed.
+    PoolModeChanged,
+    /// A ranking session was started, answered or discarded. A ranking that finishes is reported
+    /// as the change it makes instead: `PlayerUpdated` or `RanksChanged`.
+    RankingUpdated,
+    /// Course ranks were rewritten.
+    RanksChanged,
+    /// A race result was recorded.
+    ResultRecorded {
+        /// The course that was raced.
+        course: CourseId,
+    },
+    /// The placements of a race result changed.
+    ResultEdited {
+        /// The course of the edited result.
+        course: CourseId,
+    },
+    /// A race result was deleted.
+    ResultRemoved {
+        /// The course of the deleted result.
+        course: CourseId,
+    },
+    /// A new course was put in the list.
+    CourseCreated {
+        /// The new course.
+        course: CourseId,
+    },
+    /// A course's name, aliases, origin, coordinate, rank or tags changed.
+    CourseEdited {
+        /// The edited course.
+        course: CourseId,
+    },
+    /// A course was taken out of the list.
+    CourseDeleted {
+        /// The deleted course, which no longer exists.
+        course: CourseId,
+        /// Name of the deleted course.
+        name: String,
+    },
+    /// The points awarded for each placement changed.
+    PointTableChanged,
+    /// A tournament was started, advanced or ended.
+    TournamentUpdated,
+}
@@ -0,0 +1,226 @@
+//! Module defining the `GameDefinition` struct, which holds the static data of a game's courses.
+//!
+//! Saves do not own their course data. Instead, they reference a game definition by id and
+//! version, and keep only what changes during use, such as ranks. When a save is loaded, its
+//! courses are reconciled with the current definition, so fixes to the course data reach existing
+//! saves. See `CourseList::merge_game`.
+//!
+//! The built-in definition is compiled into the program. Definitions can also be placed in
+//! `GAMES_DIR` as `<id>.json`. A file there with the built-in id replaces the built-in definition.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::course::{Course, CourseId};
+use crate::{SAVES_DIR, write_atomic};
+
+/// Id of the built-in game definition.
+pub const BUILTIN_ID: &str = "mk8d";
+
+const BUILTIN_JSON: &str = include_str!("../../data/mk8d.json");
+
+static BUILTIN: LazyLock<GameDefinition> = LazyLock::new(|| {
+    serde_json::from_str(BUILTIN_JSON).expect("The built-in game definition should be valid")
+});
+
+/// Path to the directory holding game definitions other than the built-in one.
+pub static GAMES_DIR: LazyLock<PathBuf> = LazyLock::new(|| SAVES_DIR.join("games"));
+
+/// A reference to a version of a game definition, as stored in saves.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct GameRef {
+    /// Id of the game, e.g. "mk8d".
+    pub id: String,
+    /// Version of the definition.
+    pub version: u32,
+}
+
+impl fmt::Display for GameRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} v{}", self.id, self.version)
+    }
+}
+
+/// The courses of a game. The version should go up whenever the courses change.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GameDefinition {
+    /// Id of the game, e.g. "mk8d". Also the name of its file in `GAMES_DIR`, without `.json`.
+    pub id: String,
+    /// Version of the definition.
+    pub version: u32,
+    /// Every course in the game. Their ranks are only the defaults for new saves. Their ids must
+    /// be unique, and should never change, as lists linked to the game match courses by id first.
+    pub courses: Vec<Course>,
+}
+
+impl GameDefinition {
+    /// Get the built-in definition, ignoring any replacement in `GAMES_DIR`.
+    ///
+    /// # Panics
+    /// - If the built-in definition is invalid, which is a bug.
+    pub fn builtin() -> &'static GameDefinition {
+        &BUILTIN
+    }
+
+    /// Load the definition with the given id from `GAMES_DIR`, falling back to the built-in
+    /// definition if `id` is its id.
+    ///
+    /// # Errors
+    /// - If `id` is not a valid id. See `save`.
+    /// - If there is no definition with that id. The error is of kind `ErrorKind::NotFound`.
+    /// - If the definition file cannot be read or deserialized.
+    /// - If two courses share an id. The error is of kind `ErrorKind::InvalidData`.
+    pub fn load(id: &str) -> io::Result<Self> {
+        check_id(id)?;
+        match fs::read_to_string(Self::path(id)) {
+            Ok(data) => {
+                let game: Self = serde_json::from_str(&data)?;
+                let mut ids = BTreeSet::new();
+                if let Some(course) = game.courses.iter().find(|c| !ids.insert(c.id)) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Course id {} is used twice in game '{id}'", course.id),
+                    ));
+                }
+                Ok(game)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound && id == BUILTIN_ID => {
+                Ok(Self::builtin().clone())
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("There is no game '{id}'"),
+            )),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write the definition to `GAMES_DIR`, replacing any definition with the same id.
+    /// Returns the path written to.
+    ///
+    /// # Errors
+    /// - If the id is empty, or contains a path separator or a '.', so it would not name a file in
+    ///   `GAMES_DIR`. The error is of kind `ErrorKind::InvalidInput`.
+    /// - If `GAMES_DIR` cannot be created, or the definition cannot be written.
+    pub fn save(&self) -> io::Result<PathBuf> {
+        check_id(&self.id)?;
+        fs::create_dir_all(&*GAMES_DIR)?;
+        let path = Self::path(&self.id);
+        write_atomic(&path, serde_json::to_string_pretty(self)?.as_bytes())?;
+        Ok(path)
+    }
+
+    /// Get a reference to this version of the definition.
+    pub fn game_ref(&self) -> GameRef {
+        GameRef {
+            id: self.id.clone(),
+            version: self.version,
+        }
+    }
+
+    /// Find the course matching `course`: the course with the same name, or failing that, the
+    /// course in the same spot of the selection screen, which is the same course renamed.
+    pub fn find(&self, course: &Course) -> Option<usize> {
+        self.courses
+            .iter()
+            .position(|c| c.name == course.name)
+            .or_else(|| {
+                self.courses
+                    .iter()
+                    .position(|c| c.coord == course.coord && c.origin == course.origin)
+            })
+    }
+
+    fn path(id: &str) -> PathBuf {
+        GAMES_DIR.join(format!("{id}.json"))
+    }
+}
+
+// Ids name files in `GAMES_DIR`, and come from saves, so they must not lead anywhere else
+fn check_id(id: &str) -> io::Result<()> {
+    if id.is_empty() || id.contains(['/', '\\', '.']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{id}' is not a valid game id"),
+        ));
+    }
+    Ok(())
+}
+
+/// List the ids of every available game definition: the built-in one, then those in `GAMES_DIR`,
+/// sorted.
+///
+/// # Errors
+/// - If `GAMES_DIR` exists but cannot be read.
+pub fn list_games() -> io::Result<Vec<String>> {
+    let mut ids = Vec::new();
+    match fs::read_dir(&*GAMES_DIR) {
+        Ok(entries) => {
+            for entry in entries {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "json")
+                    && let Some(id) = path.file_stem().and_then(|s| s.to_str())
+                    && id != BUILTIN_ID
+                {
+                    ids.push(id.to_owned());
+                }
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    ids.sort();
+
+    ids.insert(0, BUILTIN_ID.to_owned());
+    Ok(ids)
+}
+
+/// Changes made to a list by reconciling it with a game definition. See
+/// `CourseList::merge_game`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameMerge {
+    /// The version of the game the list was linked to before, if any.
+    pub from: Option<GameRef>,
+    /// The version of the game the list is now linked to.
+    pub to: GameRef,
+    /// Ids of the courses new to the list. They were added as active courses.
+    pub added: Vec<CourseId>,
+    /// Ids of the courses no longer in the game. They are kept for their history, but are never
+    /// active again.
+    pub retired: Vec<CourseId>,
+    /// Ids of the courses that were renamed, with their old names.
+    pub renamed: Vec<(CourseId, String)>,
+}
+
+impl GameMerge {
+    /// Whether the merge changed nothing, not even the version of the game.
+    pub fn is_empty(&self) -> bool {
+        self.from.as_ref() == Some(&self.to)
+            && self.added.is_empty()
+            && self.retired.is_empty()
+            && self.renamed.is_empty()
+    }
+}
+
+impl fmt::Display for GameMerge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.from {
+            Some(from) if *from == self.to => write!(f, "Reconciled with {}", self.to)?,
+            Some(from) => write!(f, "Updated from {from} to {}", self.to)?,
+            None => write!(f, "Linked to {}", self.to)?,
+        }
+        write!(
+            f,
+            ": {} added, {} retired, {} renamed",
+            self.added.len(),
+            self.retired.len(),
+            self.renamed.len()
+        )
+    }
+}
@@ -1,19 +1,89 @@
 use serde::{Deserialize, Serialize};
 
-use super::course::Course;
+use super::course::{Course, CourseId, find_by_id};
+use super::game::GameRef;
+use super::player::Player;
+use super::prix::Prix;
+use super::veto::VetoPhase;
 
 // An action the user takes, e.g. adding or removing a course
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum Action {
-    Add(usize),
-    Remove(usize),
+    Add(CourseId),
+    Remove(CourseId),
+    // A course is rolled and removed from the list, passing the turn to pick to the next player
+    Roll(CourseId),
+    // A prix is started, replacing the prix in progress, if any
+    PrixStart {
+        prix: Prix,
+        replaced: Option<Prix>,
+    },
+    // A course is rolled from the prix in progress and removed from the list
+    PrixRoll(CourseId),
+    // The prix in progress is ended, as it was at the time
+    PrixEnd(Prix),
+    // A veto phase is started, replacing the veto phase in progress, if any
+    VetoStart {
+        veto: VetoPhase,
+        replaced: Option<VetoPhase>,
+    },
+    // A player strikes a candidate course during a veto phase
+    Veto {
+        player: usize,
+        course: CourseId,
+    },
+    // The veto phase in progress is discarded, as it was at the time
+    VetoEnd(VetoPhase),
+    // The veto phase in progress ends and its surviving candidates are raced as a prix, replacing
+    // the prix in progress, if any
+    RaceSurvivors {
+        veto: VetoPhase,
+        prix: Prix,
+        replaced: Option<Prix>,
+    },
+    // A new course is put in the list, active. Courses at its rank or below move down one rank.
+    // `unlinked` is the game the list was unlinked from, if it was linked
+    Create {
+        course: Course,
+        #[serde(default)]
+        unlinked: Option<GameRef>,
+    },
+    // A course's data is replaced. Courses between its old and new rank move to make room
+    Edit {
+        before: Course,
+        after: Course,
+        #[serde(default)]
+        unlinked: Option<GameRef>,
+    },
+    // A course is taken out of the list from `position`. Courses below it move up one rank
+    Delete {
+        course: Course,
+        position: usize,
+        active: bool,
+        #[serde(default)]
+        unlinked: Option<GameRef>,
+    },
 }
 
 impl Action {
-    fn to_string(self, courses: &[Course]) -> String {
+    fn to_string(&self, courses: &[Course], players: &[Player]) -> String {
+        let name = |id| find_by_id(courses, id).map_or("?", |c| c.name.as_str());
         match self {
-            Self::Add(i) => format!("Add({})", courses[i].name),
-            Self::Remove(i) => format!("Remove({})", courses[i].name),
+            Self::Add(id) => format!("Add({})", name(*id)),
+            Self::Remove(id) => format!("Remove({})", name(*id)),
+            Self::Roll(id) => format!("Roll({})", name(*id)),
+            Self::PrixStart { prix, .. } => format!("PrixStart({})", prix.number),
+            Self::PrixRoll(id) => format!("PrixRoll({})", name(*id)),
+            Self::PrixEnd(prix) => format!("PrixEnd({})", prix.number),
+            Self::VetoStart { veto, .. } => format!("VetoStart({})", veto.candidates.len()),
+            Self::Veto { player, course } => {
+                format!("Veto({}, {})", players[*player].name, name(*course))
+            }
+            Self::VetoEnd(_) => "VetoEnd".to_owned(),
+            Self::RaceSurvivors { prix, .. } => format!("RaceSurvivors({})", prix.number),
+            Self::Create { course, .. } => format!("Create({})", course.name),
+            Self::Edit { after, .. } => format!("Edit({})", after.name),
+            Self::Delete { course, .. } => format!("Delete({})", course.name),
         }
     }
 }
@@ -32,40 +102,64 @@ impl History {
 
     pub fn back(&mut self) -> Option<Action> {
         let res = self.past.pop()?;
-        self.future.push(res);
+        self.future.push(res.clone());
         Some(res)
     }
 
     pub fn forward(&mut self) -> Option<Action> {
         let res = self.future.pop()?;
-        self.past.push(res);
+        self.past.push(res.clone());
         Some(res)
     }
 
+    // Get removed courses that have not been undone, most recent first
+    pub fn recent_removed(&self) -> impl Iterator<Item = CourseId> {
+        self.past.iter().rev().filter_map(|a| match a {
+            Action::Remove(id) | Action::Roll(id) | Action::PrixRoll(id) => Some(*id),
+            _ => None,
+        })
+    }
+
+    // Number of rolls that have not been undone
+    pub fn rolls(&self) -> usize {
+        self.past
+            .iter()
+            .filter(|a| matches!(a, Action::Roll(_)))
+            .count()
+    }
+
     pub fn reset(&mut self) {
         self.past.clear();
         self.future.clear();
     }
 
-    pub fn to_string(&self, courses: &[Course]) -> String {
+    pub fn to_string(&self, courses: &[Course], players: &[Player]) -> String {
         let past_slice = self.past.as_slice();
         let future_slice = self.future.as_slice();
         let mut res = String::new();
 
         match past_slice {
             [] => res.push_str("None"),
-            [p1] => res.push_str(format!("None <- {}", p1.to_string(courses)).as_str()),
-            [.., p1] => res.push_str(format!("... <- {}", p1.to_string(courses)).as_str()),
+            [p1] => res.push_str(format!("None <- {}", p1.to_string(courses, players)).as_str()),
+            [.., p1] => res.push_str(format!("... <- {}", p1.to_string(courses, players)).as_str()),
         }
 
         res.push_str(" <- Current -> ");
 
         match future_slice {
             [] => res.push_str("None"),
-            [p1] => res.push_str(format!("{} -> None", p1.to_string(courses)).as_str()),
-            [.., p1] => res.push_str(format!("{} -> ...", p1.to_string(courses)).as_str()),
+            [p1] => res.push_str(format!("{} -> None", p1.to_string(courses, players)).as_str()),
+            [.., p1] => res.push_str(format!("{} -> ...", p1.to_string(courses, players)).as_str()),
         }
 
         res
     }
+
+    // Describe every past action, oldest first
+    pub fn log(&self, courses: &[Course], players: &[Player]) -> Vec<String> {
+        self.past
+            .iter()
+            .map(|a| a.to_string(courses, players))
+            .collect()
+    }
 }
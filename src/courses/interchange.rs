@@ -0,0 +1,500 @@
+//! Module for importing and exporting courses as CSV or TOML, so that course data can be
+//! maintained outside of save files, e.g. in a spreadsheet.
+//!
+//! Both formats hold one entry per course, with the fields `id`, `name`, `aliases`, `origin`,
+//! `row`, `col`, `pos`, `rank` and `tags`. In CSV, the first line is a header naming the columns,
+//! and aliases and tags are separated by ';'. In TOML, each course is a `[[courses]]` table, and
+//! aliases and tags are arrays of strings. Aliases and tags can be left out in both formats. Ids
+//! can be left out too, in which case courses without one are given the next unused ids in
+//! order, but ids should be kept once given, since lists linked to a game match courses by id.
+//!
+//! Imported data is validated as a whole: every course needs a name and a known origin, ids must
+//! be unique, coordinates must be unique and within the selection screen, and ranks must run from
+//! 1 to the number of courses without repeats. Every problem found is reported with the line it
+//! is on.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use toml::Spanned;
+
+use crate::courses::course::{COLUMNS, CUP_SIZE, Coord, Course, CourseId, Origin, ROWS};
+
+/// Separator between aliases, and between tags, in a CSV field.
+pub const CSV_LIST_SEPARATOR: char = ';';
+
+/// File format for importing and exporting courses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourseFormat {
+    /// Comma separated values with a header line.
+    Csv,
+    /// A TOML file with a `[[courses]]` table per course.
+    Toml,
+}
+
+impl CourseFormat {
+    /// Pick the format matching a file's extension: `.csv` or `.toml`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_ref() {
+            "csv" => Some(CourseFormat::Csv),
+            "toml" => Some(CourseFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// A problem found in imported course data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError {
+    /// The line the problem is on, starting at 1, if it is tied to one.
+    pub line: Option<usize>,
+    /// What the problem is.
+    pub message: String,
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+// A course as written in a TOML file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Entry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u32>,
+    name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<String>,
+    origin: String,
+    row: u8,
+    col: u8,
+    pos: u8,
+    rank: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+// A course as written in a CSV file. Unknown columns are ignored, so spreadsheets can keep notes
+#[derive(Debug, Serialize, Deserialize)]
+struct CsvEntry {
+    #[serde(default)]
+    id: Option<u32>,
+    name: String,
+    #[serde(default)]
+    aliases: String,
+    origin: String,
+    row: u8,
+    col: u8,
+    pos: u8,
+    rank: usize,
+    #[serde(default)]
+    tags: String,
+}
+
+impl From<CsvEntry> for Entry {
+    fn from(entry: CsvEntry) -> Self {
+        Entry {
+            id: entry.id,
+            name: entry.name,
+            aliases: split_list(&entry.aliases),
+            origin: entry.origin,
+            row: entry.row,
+            col: entry.col,
+            pos: entry.pos,
+            rank: entry.rank,
+            tags: split_list(&entry.tags),
+        }
+    }
+}
+
+fn split_list(field: &str) -> Vec<String> {
+    field
+        .split(CSV_LIST_SEPARATOR)
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TomlFile<T> {
+    #[serde(default = "Vec::new")]
+    courses: Vec<T>,
+}
+
+/// Write courses in the given format, in the order given.
+///
+/// # Errors
+/// - If the courses cannot be serialized, e.g. if an alias or tag contains the CSV list
+///   separator.
+pub fn export_courses(courses: &[Course], format: CourseFormat) -> io::Result<String> {
+    match format {
+        CourseFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for course in courses {
+                if course
+                    .aliases
+                    .iter()
+                    .chain(&course.tags)
+                    .any(|s| s.contains(CSV_LIST_SEPARATOR))
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "An alias or tag of {} contains '{CSV_LIST_SEPARATOR}', which \
+                            separates them in CSV",
+                            course.name
+                        ),
+                    ));
+                }
+
+                let separator = CSV_LIST_SEPARATOR.to_string();
+                writer.serialize(CsvEntry {
+                    id: Some(course.id.0),
+                    name: course.name.clone(),
+                    aliases: course.aliases.join(&separator),
+                    origin: course.origin.to_string(),
+                    row: course.coord.row(),
+                    col: course.coord.col(),
+                    pos: course.coord.pos(),
+                    rank: course.rank,
+                    tags: course.tags.join(&separator),
+                })?;
+            }
+
+            let data = writer
+                .into_inner()
+                .map_err(csv::IntoInnerError::into_error)?;
+            String::from_utf8(data).map_err(io::Error::other)
+        }
+
+        CourseFormat::Toml => {
+            let file = TomlFile {
+                courses: courses
+                    .iter()
+                    .map(|course| Entry {
+                        id: Some(course.id.0),
+                        name: course.name.clone(),
+                        aliases: course.aliases.clone(),
+                        origin: course.origin.to_string(),
+                        row: course.coord.row(),
+                        col: course.coord.col(),
+                        pos: course.coord.pos(),
+                        rank: course.rank,
+                        tags: course.tags.clone(),
+                    })
+                    .collect(),
+            };
+            toml::to_string(&file).map_err(io::Error::other)
+        }
+    }
+}
+
+/// Read and validate courses written in the given format.
+///
+/// # Errors
+/// - If the data cannot be parsed, or does not describe a valid set of courses. Every problem
+///   found is returned, each with the line it is on where possible.
+pub fn import_courses(data: &str, format: CourseFormat) -> Result<Vec<Course>, Vec<ImportError>> {
+    let (entries, errors) = match format {
+        CourseFormat::Csv => read_csv(data),
+        CourseFormat::Toml => read_toml(data),
+    };
+    validate((entries, errors))
+}
+
+// Entries that could be read, each with its line, and errors for those that could not
+type Read = (Vec<(Option<usize>, Entry)>, Vec<ImportError>);
+
+fn read_csv(data: &str) -> Read {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => return (Vec::new(), vec![csv_error(&e, None)]),
+    };
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(csv_error(&e, Some(&headers)));
+                continue;
+            }
+        };
+
+        match record.deserialize::<CsvEntry>(Some(&headers)) {
+            Ok(entry) => entries.push((csv_line(record.position()), entry.into())),
+            Err(e) => errors.push(csv_error(&e, Some(&headers))),
+        }
+    }
+
+    (entries, errors)
+}
+
+fn read_toml(data: &str) -> Read {
+    match toml::from_str::<TomlFile<Spanned<Entry>>>(data) {
+        Ok(file) => (
+            file.courses
+                .into_iter()
+                .map(|entry| (Some(line_at(data, entry.span().start)), entry.into_inner()))
+                .collect(),
+            Vec::new(),
+        ),
+        Err(e) => (
+            Vec::new(),
+            vec![ImportError {
+                line: e.span().map(|span| line_at(data, span.start)),
+                message: e.message().to_owned(),
+            }],
+        ),
+    }
+}
+
+fn validate((entries, mut errors): Read) -> Result<Vec<Course>, Vec<ImportError>> {
+    if entries.is_empty() && errors.is_empty() {
+        return Err(vec![ImportError {
+            line: None,
+            message: "No courses found".to_owned(),
+        }]);
+    }
+
+    // With entries missing, the number of courses is unknown, so ranks can only be checked for
+    // repeats
+    let count = errors.is_empty().then_some(entries.len());
+    let mut courses = Vec::with_capacity(entries.len());
+    let mut ids: HashMap<u32, Option<usize>> = HashMap::new();
+    let mut coords: HashMap<Coord, Option<usize>> = HashMap::new();
+    let mut ranks: HashMap<usize, Option<usize>> = HashMap::new();
+
+    for (line, entry) in entries {
+        let mut error = |message: String| errors.push(ImportError { line, message });
+
+        let name = entry.name.trim();
+        if name.is_empty() {
+            error("The course has no name".to_owned());
+        }
+
+        if let Some(id) = entry.id
+            && let Some(other) = ids.insert(id, line)
+        {
+            error(format!("Id {id} is already used{}", on_line(other)));
+        }
+
+        let origin = entry.origin.parse::<Origin>().map_err(&mut error).ok();
+
+        let coord = Coord::new(entry.row, entry.col, entry.pos);
+        if !coord.is_valid() {
+            error(format!(
+                "Invalid coordinate {coord}: row runs from 1 to {ROWS}, column from 1 to \
+                {COLUMNS}, and position from 1 to {CUP_SIZE}"
+            ));
+        } else if let Some(other) = coords.insert(coord, line) {
+            error(format!(
+                "Coordinate {coord} is already used{}",
+                on_line(other)
+            ));
+        }
+
+        if entry.rank == 0 {
+            error("Ranks start at 1".to_owned());
+        } else if let Some(count) = count
+            && entry.rank > count
+        {
+            error(format!(
+                "Rank {} is out of range: with {count} courses, ranks run from 1 to {count}",
+                entry.rank
+            ));
+        } else if let Some(other) = ranks.insert(entry.rank, line) {
+            error(format!(
+                "Rank {} is already used{}",
+                entry.rank,
+                on_line(other)
+            ));
+        }
+
+        if let Some(origin) = origin {
+            let id = CourseId(entry.id.unwrap_or_default());
+            let mut course = Course::new(id, entry.rank, coord, origin, name);
+            course.aliases = entry.aliases;
+            course.tags = entry.tags;
+            courses.push((entry.id.is_some(), course));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(assign_ids(courses))
+    } else {
+        errors.sort_by_key(|e| e.line);
+        Err(errors)
+    }
+}
+
+// Give courses without an id the next unused ids, in order. The flag tells whether a course has
+// an id
+fn assign_ids(courses: Vec<(bool, Course)>) -> Vec<Course> {
+    let mut next = courses
+        .iter()
+        .filter(|(has_id, _)| *has_id)
+        .map(|(_, course)| course.id.0.saturating_add(1))
+        .max()
+        .unwrap_or_default();
+
+    courses
+        .into_iter()
+        .map(|(has_id, mut course)| {
+            if !has_id {
+                course.id = CourseId(next);
+                next = next.saturating_add(1);
+            }
+            course
+        })
+        .collect()
+}
+
+fn csv_error(e: &csv::Error, headers: Option<&csv::StringRecord>) -> ImportError {
+    let message = match e.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => {
+            let column = err
+                .field()
+                .and_then(|i| headers?.get(usize::try_from(i).ok()?));
+            match column {
+                Some(column) => format!("Invalid value in column '{column}': {}", err.kind()),
+                None => err.kind().to_string(),
+            }
+        }
+        csv::ErrorKind::UnequalLengths {
+            expected_len, len, ..
+        } => format!("Expected {expected_len} fields, found {len}"),
+        _ => e.to_string(),
+    };
+
+    ImportError {
+        line: csv_line(e.position()),
+        message,
+    }
+}
+
+fn csv_line(position: Option<&csv::Position>) -> Option<usize> {
+    position.and_then(|p| usize::try_from(p.line()).ok())
+}
+
+// Line number of the byte at `offset`
+fn line_at(data: &str, offset: usize) -> usize {
+    data.as_bytes()
+        .iter()
+        .take(offset)
+        .filter(|&&b| b == b'\n')
+        .count()
+        + 1
+}
+
+fn on_line(line: Option<usize>) -> String {
+    line.map_or_else(String::new, |line| format!(" on line {line}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV_HEADER: &str = "id,name,aliases,origin,row,col,pos,rank,tags\n";
+
+    fn lines(errors: &[ImportError]) -> Vec<Option<usize>> {
+        errors.iter().map(|e| e.line).collect()
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let data = format!(
+            "{CSV_HEADER}0,Mario Kart Stadium,MKS;Stadium,MK8,1,1,1,2,\n\
+            1,Water Park,,MK8,1,1,2,1,wet\n"
+        );
+        let courses = import_courses(&data, CourseFormat::Csv).unwrap();
+        assert_eq!(courses[0].aliases, ["MKS", "Stadium"]);
+        assert_eq!(courses[1].tags, ["wet"]);
+
+        let exported = export_courses(&courses, CourseFormat::Csv).unwrap();
+        assert_eq!(exported, data);
+    }
+
+    #[test]
+    fn csv_errors_name_their_lines() {
+        let data = format!(
+            "{CSV_HEADER}0,Mario Kart Stadium,,MK8,1,1,1,1,\n\
+            0,Water Park,,Nope,1,1,1,2,\n\
+            2,Sweet Sweet Canyon,,MK8,5,7,1,3,\n\
+            3,Thwomp Ruins,,MK8,one,1,4,4,\n"
+        );
+        let errors = import_courses(&data, CourseFormat::Csv).unwrap_err();
+        assert_eq!(
+            lines(&errors),
+            [Some(3), Some(3), Some(3), Some(4), Some(5)]
+        );
+        assert!(errors[0].to_string().starts_with("line 3: "));
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.message == "Id 0 is already used on line 2")
+        );
+        assert!(
+            errors
+                .iter()
+                .any(|e| e.message == "Coordinate 1-1-1 is already used on line 2")
+        );
+        assert!(errors[3].message.starts_with("Invalid coordinate 5-7-1"));
+        assert!(errors[4].message.contains("'row'"));
+    }
+
+    #[test]
+    fn toml_errors_name_their_lines() {
+        let data = "\
+            [[courses]]\n\
+            name = \"Mario Kart Stadium\"\n\
+            origin = \"MK8\"\n\
+            row = 1\n\
+            col = 1\n\
+            pos = 5\n\
+            rank = 1\n\
+            \n\
+            [[courses]]\n\
+            name = \"\"\n\
+            origin = \"MK8\"\n\
+            row = 1\n\
+            col = 1\n\
+            pos = 2\n\
+            rank = 1\n";
+        let errors = import_courses(data, CourseFormat::Toml).unwrap_err();
+        assert_eq!(lines(&errors), [Some(1), Some(9), Some(9)]);
+        assert!(errors[0].message.starts_with("Invalid coordinate 1-1-5"));
+        assert_eq!(errors[2].message, "Rank 1 is already used on line 1");
+
+        let errors = import_courses("[[courses]]\nname = 1\n", CourseFormat::Toml).unwrap_err();
+        assert_eq!(lines(&errors), [Some(2)]);
+    }
+
+    #[test]
+    fn missing_ids_are_assigned_in_order() {
+        let data = format!(
+            "{CSV_HEADER},Mario Kart Stadium,,MK8,1,1,1,1,\n\
+            7,Water Park,,MK8,1,1,2,2,\n\
+            ,Sweet Sweet Canyon,,MK8,1,1,3,3,\n"
+        );
+        let courses = import_courses(&data, CourseFormat::Csv).unwrap();
+        let ids: Vec<u32> = courses.iter().map(|c| c.id.0).collect();
+        assert_eq!(ids, [8, 7, 9]);
+    }
+}
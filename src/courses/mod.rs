@@ -1,6 +1,22 @@
 //! Module defining the `CourseList` struct that holds state pertaining to a given course list, as
 //! well as the `Course` structs defining a specific course.
 
+pub mod aggregate;
 pub mod course;
 pub mod course_list;
+pub mod edit;
+pub mod event;
+pub mod game;
 mod history;
+pub mod interchange;
+pub mod observer;
+pub mod player;
+pub mod prix;
+pub mod ranking;
+pub mod ratings;
+pub mod results;
+pub mod search;
+pub mod session;
+pub mod tiers;
+pub mod tournament;
+pub mod veto;
@@ -0,0 +1,238 @@
+//! Module defining the `Observer` trait, used to react to changes made to a `CourseList`.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use std::time::Instant;
+
+use super::course::CourseId;
+use super::course_list::CourseList;
+use super::event::Event;
+use super::prix::Prix;
+
+/// Something that reacts to changes made to a `CourseList`, e.g. by printing them, writing files
+/// or saving the list. Register one with `CourseList::subscribe`.
+///
+/// Any `FnMut(&Change)` closure is an observer.
+pub trait Observer {
+    /// Called after every change made to the list.
+    ///
+    /// # Errors
+    /// - If the observer could not react to the change, e.g. could not write a file. The change
+    ///   stands regardless, and the list keeps the error for its owner to report. See
+    ///   `CourseList::take_observer_errors`.
+    fn on_change(&mut self, change: &Change) -> io::Result<()>;
+
+    /// Called by `CourseList::flush_observers` to catch up on work held back from earlier
+    /// changes, e.g. saving changes an `Autosave` has not saved yet. `closing` is true when the
+    /// list is about to be closed, so nothing should be held back any longer. Does nothing by
+    /// default.
+    ///
+    /// # Errors
+    /// - As for `on_change`.
+    fn on_flush(&mut self, _list: &CourseList, _closing: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Get the time from which the observer has work to catch up on with `on_flush`, if any.
+    /// `None` by default.
+    fn flush_due(&self) -> Option<Instant> {
+        None
+    }
+}
+
+impl<F: FnMut(&Change)> Observer for F {
+    fn on_change(&mut self, change: &Change) -> io::Result<()> {
+        self(change);
+        Ok(())
+    }
+}
+
+/// The part of a `CourseList`'s state that is kept for comparison across a change: the active
+/// courses and the prix. Nothing else is kept, so use `Change::event` to tell what else changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Active courses.
+    pub current: BTreeSet<CourseId>,
+    /// The prix in progress, if any.
+    pub prix: Option<Prix>,
+}
+
+/// A change made to a `CourseList`, as given to an `Observer`.
+#[derive(Debug)]
+pub struct Change<'a> {
+    /// What happened.
+    pub event: &'a Event,
+    /// Active courses and prix just before the change.
+    pub before: &'a Snapshot,
+    /// The list just after the change.
+    pub after: &'a CourseList,
+}
+
+impl Change<'_> {
+    /// Get the courses the change made active.
+    pub fn added(&self) -> Vec<CourseId> {
+        self.after
+            .get_current()
+            .filter(|i| !self.before.current.contains(i))
+            .collect()
+    }
+
+    /// Get the courses the change made inactive.
+    pub fn removed(&self) -> Vec<CourseId> {
+        let after: BTreeSet<CourseId> = self.after.get_current().collect();
+        self.before.current.difference(&after).copied().collect()
+    }
+}
+
+// Registered observers. Observers are not serialized, so this only needs to be skippable
+#[derive(Default)]
+pub(crate) struct Observers(pub(crate) Vec<Box<dyn Observer>>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use super::super::course::{Coord, Course, Origin};
+    use super::*;
+
+    fn list_of(count: u8) -> CourseList {
+        let courses = (0..count)
+            .map(|n| {
+                let name = format!("Course {n}");
+                let coord = Coord::new(1, 1, 1 + n);
+                Course::new(
+                    CourseId(n.into()),
+                    usize::from(n) + 1,
+                    coord,
+                    Origin::MK8,
+                    &name,
+                )
+            })
+            .collect();
+        CourseList::from_courses("test.json", courses)
+    }
+
+    // Fails every change, and counts flushes
+    struct Failing {
+        flushes: Rc<Cell<usize>>,
+        due: Option<Instant>,
+    }
+
+    impl Observer for Failing {
+        fn on_change(&mut self, change: &Change) -> io::Result<()> {
+            Err(io::Error::other(format!("{:?}", change.event)))
+        }
+
+        fn on_flush(&mut self, _list: &CourseList, closing: bool) -> io::Result<()> {
+            self.flushes.set(self.flushes.get() + 1);
+            if closing {
+                self.due = None;
+            }
+            Ok(())
+        }
+
+        fn flush_due(&self) -> Option<Instant> {
+            self.due
+        }
+    }
+
+    #[test]
+    fn changes_compare_against_the_last_change() {
+        let mut list = list_of(3);
+        // Changes before subscribing are not reported
+        list.remove(CourseId(0));
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let recorded = Rc::clone(&changes);
+        list.subscribe(move |change: &Change| {
+            let moved = (change.event.clone(), change.added(), change.removed());
+            recorded.borrow_mut().push(moved);
+        });
+
+        list.remove(CourseId(1));
+        list.roll_back().unwrap();
+        list.add(CourseId(0));
+        assert_eq!(
+            *changes.borrow(),
+            [
+                (
+                    Event::CourseRemoved {
+                        course: CourseId(1)
+                    },
+                    vec![],
+                    vec![CourseId(1)]
+                ),
+                (Event::Undo, vec![CourseId(1)], vec![]),
+                (
+                    Event::CourseAdded {
+                        course: CourseId(0)
+                    },
+                    vec![CourseId(0)],
+                    vec![]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_kept_until_taken() {
+        let mut list = list_of(3);
+        let flushes = Rc::new(Cell::new(0));
+        list.subscribe(Failing {
+            flushes: Rc::clone(&flushes),
+            due: None,
+        });
+        let changes = Rc::new(Cell::new(0));
+        let counted = Rc::clone(&changes);
+        list.subscribe(move |_: &Change| counted.set(counted.get() + 1));
+
+        list.remove(CourseId(0));
+        list.remove(CourseId(1));
+        // Failing observers neither undo the change nor stop the others
+        assert_eq!(list.get_current().collect::<Vec<_>>(), [CourseId(2)]);
+        assert_eq!(changes.get(), 2);
+
+        let errors: Vec<String> = list
+            .take_observer_errors()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("CourseId(0)"));
+        assert!(list.take_observer_errors().is_empty());
+    }
+
+    #[test]
+    fn flushes_reach_every_observer() {
+        let mut list = list_of(1);
+        assert_eq!(list.next_flush(), None);
+
+        let now = Instant::now();
+        let flushes = Rc::new(Cell::new(0));
+        for due in [now + Duration::from_secs(5), now] {
+            list.subscribe(Failing {
+                flushes: Rc::clone(&flushes),
+                due: Some(due),
+            });
+        }
+        assert_eq!(list.next_flush(), Some(now));
+
+        list.flush_observers(false);
+        assert_eq!(flushes.get(), 2);
+        assert_eq!(list.next_flush(), Some(now));
+
+        list.flush_observers(true);
+        assert_eq!(flushes.get(), 4);
+        assert_eq!(list.next_flush(), None);
+        assert!(list.take_observer_errors().is_empty());
+    }
+}
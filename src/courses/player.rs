@@ -0,0 +1,52 @@
+//! Module defining the `Player` struct, which holds per-player data stored in a save, and the
+//! `PoolMode` enum, which decides how players' bans shape the pool of courses to roll from.
+
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::course::CourseId;
+
+/// How the courses players have banned limit which courses can be rolled.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoolMode {
+    /// Only roll courses that no present player has banned.
+    #[default]
+    Everyone,
+    /// Present players take turns picking. Only roll courses the current picker has not banned.
+    Rotation,
+}
+
+/// A player.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Player {
+    /// The player's name. Names are unique within a `CourseList`.
+    pub name: String,
+    /// The player's personal ranking of courses, as course ids, best first. Empty if the player
+    /// has not ranked any courses.
+    #[serde(default)]
+    pub ranking: Vec<CourseId>,
+    /// Ids of courses the player does not want to race.
+    #[serde(default)]
+    pub banned: BTreeSet<CourseId>,
+    /// Whether the player is playing in the current session.
+    #[serde(default = "present_by_default")]
+    pub present: bool,
+}
+
+impl Player {
+    /// Create a new, present player with no ranking and no banned courses.
+    pub fn new(name: &str) -> Self {
+        Player {
+            name: name.to_owned(),
+            ranking: Vec::new(),
+            banned: BTreeSet::new(),
+            present: true,
+        }
+    }
+}
+
+// Players saved before presence was tracked were all playing
+fn present_by_default() -> bool {
+    true
+}
@@ -0,0 +1,52 @@
+//! Module defining the `Prix` struct, which tracks an in-progress tiered sub-list.
+
+use serde::{Deserialize, Serialize};
+
+use super::course::CourseId;
+
+/// A course drawn into a prix, along with the label of the tier it was drawn from.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PrixEntry {
+    /// Label of the tier the course was drawn from, e.g. "Tier 1 (ranks 1-24)".
+    pub label: String,
+    /// Id of the course.
+    pub course: CourseId,
+}
+
+/// An in-progress tiered sub-list. Courses are removed from the main list as they are raced, so
+/// a prix that is abandoned partway through keeps the courses raced so far removed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prix {
+    /// Number of the prix, counting up from 1 within a `CourseList`.
+    #[serde(default)]
+    pub number: usize,
+    /// Courses that have not been raced yet.
+    pub remaining: Vec<PrixEntry>,
+    /// Courses that have been raced, in order.
+    pub raced: Vec<PrixEntry>,
+}
+
+impl Prix {
+    /// Whether every course in the prix has been raced.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.remaining.is_empty()
+    }
+
+    /// Mark a course as raced. Does nothing if the course is not remaining in the prix.
+    pub(crate) fn mark_raced(&mut self, course: CourseId) {
+        if let Some(pos) = self.remaining.iter().position(|e| e.course == course) {
+            let entry = self.remaining.remove(pos);
+            self.raced.push(entry);
+        }
+    }
+
+    /// Mark a raced course as remaining again. Does nothing if the course has not been raced in
+    /// the prix.
+    pub(crate) fn unmark_raced(&mut self, course: CourseId) {
+        if let Some(pos) = self.raced.iter().rposition(|e| e.course == course) {
+            let entry = self.raced.remove(pos);
+            self.remaining.push(entry);
+        }
+    }
+}
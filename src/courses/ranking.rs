@@ -0,0 +1,172 @@
+//! Module defining the `RankingSession` struct, which builds a full course ranking out of
+//! pairwise "A or B?" comparisons.
+
+use serde::{Deserialize, Serialize};
+
+use super::course::CourseId;
+
+/// An in-progress ranking. Courses are inserted one at a time into a sorted list using binary
+/// search, so each insertion takes at most ⌈log2(k + 1)⌉ comparisons, where k is the number of
+/// courses already ranked. The session is serializable so it can be resumed later.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RankingSession {
+    /// Name of the player whose personal ranking is being built, or `None` if the ranking is for
+    /// the course list itself.
+    #[serde(default)]
+    pub player: Option<String>,
+    /// Courses ranked so far, best first.
+    sorted: Vec<CourseId>,
+    /// Courses that have yet to be inserted. The last one is being inserted right now.
+    pending: Vec<CourseId>,
+    /// Lower bound (inclusive) of the insertion position of the current course in `sorted`.
+    lo: usize,
+    /// Upper bound (exclusive) of the insertion position of the current course in `sorted`.
+    hi: usize,
+}
+
+impl RankingSession {
+    /// Start ranking the given courses, optionally on behalf of a player.
+    pub fn new(courses: impl IntoIterator<Item = CourseId>, player: Option<String>) -> Self {
+        let mut pending: Vec<CourseId> = courses.into_iter().collect();
+        pending.reverse();
+
+        let mut res = RankingSession {
+            player,
+            sorted: Vec::new(),
+            pending,
+            lo: 0,
+            hi: 0,
+        };
+        res.settle();
+        res
+    }
+
+    /// Check whether a course is part of the ranking, ranked yet or not.
+    pub fn contains(&self, course: CourseId) -> bool {
+        self.sorted.contains(&course) || self.pending.contains(&course)
+    }
+
+    /// Get the next pair of courses to compare, or `None` if the ranking is finished.
+    pub fn next_comparison(&self) -> Option<(CourseId, CourseId)> {
+        let &candidate = self.pending.last()?;
+        let pivot = self.sorted[self.midpoint()];
+        Some((candidate, pivot))
+    }
+
+    /// Answer the comparison returned by `next_comparison`. `first_is_better` should be true if
+    /// the first course of the pair is better than the second. Does nothing if the ranking is
+    /// finished.
+    pub fn answer(&mut self, first_is_better: bool) {
+        if self.is_finished() {
+            return;
+        }
+
+        let mid = self.midpoint();
+        if first_is_better {
+            self.hi = mid;
+        } else {
+            self.lo = mid + 1;
+        }
+        self.settle();
+    }
+
+    /// Whether every course has been ranked.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Number of courses ranked so far, and the total number of courses being ranked.
+    pub fn progress(&self) -> (usize, usize) {
+        let ranked = self.sorted.len();
+        (ranked, ranked + self.pending.len())
+    }
+
+    /// Get the finished ranking, best first, or `None` if it is not finished.
+    pub fn ranking(&self) -> Option<&[CourseId]> {
+        self.is_finished().then_some(self.sorted.as_slice())
+    }
+
+    fn midpoint(&self) -> usize {
+        self.lo.midpoint(self.hi)
+    }
+
+    // Insert the current course once its position is known, and move on to the next one.
+    fn settle(&mut self) {
+        while self.lo == self.hi {
+            let Some(candidate) = self.pending.pop() else {
+                return;
+            };
+
+            self.sorted.insert(self.lo, candidate);
+            self.lo = 0;
+            self.hi = self.sorted.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Answer every comparison by the position of the courses in `truth`, best first, counting the
+    // comparisons made
+    fn answer_all(session: &mut RankingSession, truth: &[u32]) -> usize {
+        let position = |id: CourseId| truth.iter().position(|&t| t == id.0);
+        let mut comparisons = 0;
+        while let Some((a, b)) = session.next_comparison() {
+            session.answer(position(a) < position(b));
+            comparisons += 1;
+        }
+        comparisons
+    }
+
+    fn ids(ids: &[u32]) -> Vec<CourseId> {
+        ids.iter().copied().map(CourseId).collect()
+    }
+
+    #[test]
+    fn answers_give_the_true_order() {
+        let truth = [5, 2, 7, 0, 3, 6, 1, 4];
+        let mut session = RankingSession::new(ids(&[0, 1, 2, 3, 4, 5, 6, 7]), None);
+        assert_eq!(session.progress(), (1, 8));
+        assert_eq!(session.ranking(), None);
+
+        let comparisons = answer_all(&mut session, &truth);
+        assert_eq!(session.ranking(), Some(ids(&truth).as_slice()));
+        assert_eq!(session.progress(), (8, 8));
+        // Inserting the k-th course takes at most ⌈log2(k)⌉ comparisons: 0+1+2+2+3+3+3+3
+        assert!(comparisons <= 17, "{comparisons} comparisons");
+    }
+
+    #[test]
+    fn sessions_resume_where_they_left_off() {
+        let truth = [3, 1, 4, 0, 2];
+        let mut session = RankingSession::new(ids(&[0, 1, 2, 3, 4]), Some("Alice".to_owned()));
+        let position = |id: CourseId| truth.iter().position(|&t| t == id.0);
+        for _ in 0..3 {
+            let (a, b) = session.next_comparison().unwrap();
+            session.answer(position(a) < position(b));
+        }
+
+        let data = serde_json::to_string(&session).unwrap();
+        let mut resumed: RankingSession = serde_json::from_str(&data).unwrap();
+        assert_eq!(resumed.player.as_deref(), Some("Alice"));
+        assert_eq!(resumed.next_comparison(), session.next_comparison());
+
+        answer_all(&mut resumed, &truth);
+        assert_eq!(resumed.ranking(), Some(ids(&truth).as_slice()));
+    }
+
+    #[test]
+    fn small_sessions_finish_right_away() {
+        let session = RankingSession::new(ids(&[9]), None);
+        assert!(session.is_finished());
+        assert_eq!(session.ranking(), Some(ids(&[9]).as_slice()));
+        assert_eq!(session.next_comparison(), None);
+
+        let mut session = RankingSession::new([], None);
+        session.answer(true);
+        assert_eq!(session.ranking(), Some([].as_slice()));
+    }
+}
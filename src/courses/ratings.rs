@@ -0,0 +1,112 @@
+//! Module for computing player skill ratings from recorded race results.
+//!
+//! Ratings are never stored. They are recomputed from the result log on demand, so editing or
+//! deleting a result is always reflected in every rating.
+
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use super::course::CourseId;
+use super::results::RaceResult;
+
+/// Parameters of the rating engine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RatingConfig {
+    /// Rating every player starts with.
+    pub initial: f64,
+    /// Largest rating change a player can get from a single race.
+    pub k: f64,
+}
+
+impl Default for RatingConfig {
+    fn default() -> Self {
+        RatingConfig {
+            initial: 1500.0,
+            k: 32.0,
+        }
+    }
+}
+
+/// Ratings computed from a result log.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Ratings {
+    /// Each player's current rating, by name.
+    pub overall: BTreeMap<String, f64>,
+    /// Each player's current rating on each course, by course id, then by name.
+    pub per_course: BTreeMap<CourseId, BTreeMap<String, f64>>,
+    /// Each player's overall rating after every race they took part in, oldest first, by name.
+    pub history: BTreeMap<String, Vec<f64>>,
+}
+
+impl Ratings {
+    /// Compute ratings by replaying `results` in order. Each race is scored as a set of head to
+    /// head matchups between every pair of players in it (multiplayer Elo): a player's rating
+    /// moves by `k / (players - 1)` times the sum of their actual minus expected score against
+    /// each opponent.
+    pub fn compute(results: &[RaceResult], config: &RatingConfig) -> Self {
+        let mut res = Ratings::default();
+
+        for result in results {
+            update(&mut res.overall, &result.placements, config);
+            let course = res.per_course.entry(result.course).or_default();
+            update(course, &result.placements, config);
+
+            for player in result.placements.keys() {
+                res.history
+                    .entry(player.clone())
+                    .or_default()
+                    .push(res.overall.get(player).copied().unwrap_or(config.initial));
+            }
+        }
+
+        res
+    }
+
+    /// Players sorted by overall rating, best first.
+    pub fn leaderboard(&self) -> Vec<(&str, f64)> {
+        let mut res: Vec<(&str, f64)> = self
+            .overall
+            .iter()
+            .map(|(name, &rating)| (name.as_str(), rating))
+            .collect();
+        res.sort_by(|a, b| b.1.total_cmp(&a.1));
+        res
+    }
+}
+
+fn update(
+    ratings: &mut BTreeMap<String, f64>,
+    placements: &BTreeMap<String, u8>,
+    config: &RatingConfig,
+) {
+    if placements.len() < 2 {
+        return;
+    }
+
+    let before: Vec<(&String, u8, f64)> = placements
+        .iter()
+        .map(|(name, &placement)| {
+            let rating = ratings.get(name).copied().unwrap_or(config.initial);
+            (name, placement, rating)
+        })
+        .collect();
+
+    let opponents = f64::from(u32::try_from(before.len() - 1).unwrap_or(u32::MAX));
+    for &(name, placement, rating) in &before {
+        let delta: f64 = before
+            .iter()
+            .filter(|(other, _, _)| *other != name)
+            .map(|&(_, other_placement, other_rating)| {
+                let expected = 1.0 / (1.0 + 10_f64.powf((other_rating - rating) / 400.0));
+                let actual = match placement.cmp(&other_placement) {
+                    Ordering::Less => 1.0,
+                    Ordering::Equal => 0.5,
+                    Ordering::Greater => 0.0,
+                };
+                actual - expected
+            })
+            .sum();
+
+        ratings.insert(name.clone(), rating + config.k / opponents * delta);
+    }
+}
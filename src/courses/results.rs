@@ -0,0 +1,202 @@
+//! Module defining race results and the standings computed from them.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::course::CourseId;
+use super::tournament::MatchRef;
+
+/// Points awarded for each placement. The first entry is for first place, the second for second
+/// place, and so on. Placements past the end of the table are worth no points.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PointTable(pub Vec<u32>);
+
+impl PointTable {
+    /// Get the points awarded for a placement, starting at 1.
+    pub fn points(&self, placement: u8) -> u32 {
+        usize::from(placement)
+            .checked_sub(1)
+            .and_then(|i| self.0.get(i))
+            .copied()
+            .unwrap_or(0)
+    }
+}
+
+impl Default for PointTable {
+    /// The MK8D table for a 12-racer race.
+    fn default() -> Self {
+        PointTable(vec![15, 12, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1])
+    }
+}
+
+/// The result of one race.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RaceResult {
+    /// Id of the course raced.
+    pub course: CourseId,
+    /// Number of the prix the race belonged to, if any.
+    pub prix: Option<usize>,
+    /// The tournament match the race belonged to, if any.
+    #[serde(default)]
+    pub tournament_match: Option<MatchRef>,
+    /// Each player's placement, starting at 1, by player name.
+    pub placements: BTreeMap<String, u8>,
+}
+
+/// A player's totals over a set of races.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Standing {
+    /// The player's name.
+    pub player: String,
+    /// Total points scored.
+    pub points: u32,
+    /// Number of races the player took part in.
+    pub races: usize,
+    /// Number of races the player won.
+    pub wins: usize,
+}
+
+/// The player with the best average placement on a course.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CourseLeader {
+    /// Id of the course.
+    pub course: CourseId,
+    /// The player's name.
+    pub player: String,
+    /// The player's average placement on the course.
+    pub average: f64,
+    /// Number of times the player raced the course.
+    pub races: usize,
+}
+
+/// Total up the given results into standings, best first. Ties are broken by wins, then by name.
+pub fn standings<'a>(
+    results: impl IntoIterator<Item = &'a RaceResult>,
+    table: &PointTable,
+) -> Vec<Standing> {
+    let mut totals: BTreeMap<&str, Standing> = BTreeMap::new();
+
+    for result in results {
+        for (player, &placement) in &result.placements {
+            let standing = totals.entry(player).or_insert_with(|| Standing {
+                player: player.clone(),
+                points: 0,
+                races: 0,
+                wins: 0,
+            });
+
+            standing.points += table.points(placement);
+            standing.races += 1;
+            standing.wins += usize::from(placement == 1);
+        }
+    }
+
+    let mut res: Vec<Standing> = totals.into_values().collect();
+    res.sort_by(|a, b| b.points.cmp(&a.points).then(b.wins.cmp(&a.wins)));
+    res
+}
+
+/// Find the player with the best average placement on each course that has results, ordered by
+/// course id. Ties are broken by number of races, then by name.
+pub fn course_leaders<'a>(results: impl IntoIterator<Item = &'a RaceResult>) -> Vec<CourseLeader> {
+    // Course -> player -> (sum of placements, races)
+    let mut totals: BTreeMap<CourseId, BTreeMap<&str, (u32, u32)>> = BTreeMap::new();
+
+    for result in results {
+        let course = totals.entry(result.course).or_default();
+        for (player, &placement) in &result.placements {
+            let entry = course.entry(player).or_default();
+            entry.0 += u32::from(placement);
+            entry.1 += 1;
+        }
+    }
+
+    totals
+        .into_iter()
+        .filter_map(|(course, players)| {
+            players
+                .into_iter()
+                .map(|(player, (sum, races))| (player, f64::from(sum) / f64::from(races), races))
+                .min_by(|a, b| a.1.total_cmp(&b.1).then(b.2.cmp(&a.2)))
+                .map(|(player, average, races)| CourseLeader {
+                    course,
+                    player: player.to_owned(),
+                    average,
+                    races: races as usize,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(course: u32, placements: &[(&str, u8)]) -> RaceResult {
+        RaceResult {
+            course: CourseId(course),
+            prix: None,
+            tournament_match: None,
+            placements: placements
+                .iter()
+                .map(|&(name, placement)| (name.to_owned(), placement))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn points_by_placement() {
+        let table = PointTable::default();
+        assert_eq!(table.points(1), 15);
+        assert_eq!(table.points(12), 1);
+        assert_eq!(table.points(13), 0);
+        assert_eq!(table.points(0), 0);
+    }
+
+    #[test]
+    fn standings_break_ties_by_wins_then_name() {
+        let results = [
+            result(0, &[("Carol", 1), ("Alice", 2), ("Bob", 3)]),
+            result(1, &[("Bob", 1), ("Alice", 2), ("Carol", 4)]),
+            result(2, &[("Dave", 1), ("Eve", 1)]),
+        ];
+        let table = PointTable(vec![3, 2, 1]);
+
+        let standings = standings(&results, &table);
+        let order: Vec<(&str, u32, usize)> = standings
+            .iter()
+            .map(|s| (s.player.as_str(), s.points, s.wins))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("Bob", 4, 1),
+                ("Alice", 4, 0),
+                ("Carol", 3, 1),
+                ("Dave", 3, 1),
+                ("Eve", 3, 1),
+            ]
+        );
+        assert_eq!(standings[0].races, 2);
+    }
+
+    #[test]
+    fn leaders_have_the_best_average_placement() {
+        let results = [
+            result(0, &[("Alice", 1), ("Bob", 2)]),
+            result(0, &[("Alice", 3), ("Bob", 2)]),
+            result(1, &[("Carol", 2)]),
+            result(1, &[("Bob", 2), ("Carol", 2)]),
+        ];
+
+        let leaders = course_leaders(&results);
+        let found: Vec<(u32, &str, usize)> = leaders
+            .iter()
+            .map(|l| (l.course.0, l.player.as_str(), l.races))
+            .collect();
+        // Alice and Bob both average 2 on course 0, and both raced it twice, so the name decides.
+        // On course 1, Carol raced more often than Bob
+        assert_eq!(found, [(0, "Alice", 2), (1, "Carol", 2)]);
+    }
+}
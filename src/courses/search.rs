@@ -0,0 +1,325 @@
+//! Module for searching courses by name, alias, origin or coordinate, with typo tolerance and
+//! results ranked by relevance.
+//!
+//! Searches ignore case. Each course gets a score out of `MAX_SCORE` from the best of:
+//! - The search being a course's name or one of its aliases, or the start or a part of one, e.g.
+//!   "DKJ" or "coconut". The search may start with the course's origin, e.g. "3DS RR".
+//! - The search being a course's coordinate, e.g. "3-1-4", or the cup it is in, e.g. "3-1".
+//! - The search being only an origin, e.g. "Wii", which matches every course from that game.
+//! - Every word of the search matching a word of the name or an alias, or the origin, e.g.
+//!   "castle bowser". Words match whole, as the start of a word, or with a typo or two,
+//!   depending on their length. In searches of three words or more, one word may be left
+//!   unmatched, so "bowser castle 3ds" still finds the Bowser's Castles.
+
+use std::cmp::Reverse;
+
+use super::course::{Course, CourseId, Origin};
+
+/// Highest possible score: the search is exactly a course's name, alias or coordinate.
+pub const MAX_SCORE: u32 = 100;
+
+/// Lowest score of a strong match: the search is a name or alias, or the start of one.
+pub const STRONG_MATCH: u32 = 90;
+
+const PART_SCORE: u32 = 70;
+const CUP_SCORE: u32 = 60;
+const ORIGIN_SCORE: u32 = 40;
+
+// Highest score of a match made word by word, when every word matches whole
+const WORDS_SCORE: u32 = 60;
+
+// Points for each word of a search matched word by word
+const WORD_WHOLE: u32 = 10;
+const WORD_START: u32 = 9;
+const WORD_TYPO: u32 = 8;
+// An origin narrows a search down, but says less about which course is meant than its name
+const WORD_ORIGIN: u32 = 8;
+
+/// A course found by a search, with how well it matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Id of the course.
+    pub course: CourseId,
+    /// How well the course matched, up to `MAX_SCORE`.
+    pub score: u32,
+}
+
+/// Search courses, returning those that match, best match first. Courses that match equally
+/// well keep their order. A blank search matches every course equally.
+pub fn search<'a>(
+    courses: impl IntoIterator<Item = &'a Course>,
+    searched: &str,
+) -> Vec<SearchMatch> {
+    let mut matches: Vec<SearchMatch> = courses
+        .into_iter()
+        .filter_map(|course| {
+            Some(SearchMatch {
+                course: course.id,
+                score: score(course, searched)?,
+            })
+        })
+        .collect();
+    matches.sort_by_key(|m| Reverse(m.score));
+    matches
+}
+
+/// Get the best of the results of a search, if it is the only strong match. See `STRONG_MATCH`.
+/// `matches` should be sorted as `search` sorts them. A search that is an origin, e.g. "DS", has
+/// no strong match even if it is also an alias, since it may mean any course from that game.
+pub fn strong_match(matches: &[SearchMatch], searched: &str) -> Option<CourseId> {
+    if searched.parse::<Origin>().is_ok() {
+        return None;
+    }
+    match matches {
+        [best, rest @ ..] if best.score >= STRONG_MATCH => rest
+            .first()
+            .is_none_or(|next| next.score < STRONG_MATCH)
+            .then_some(best.course),
+        _ => None,
+    }
+}
+
+/// Score how well a search matches a course, or get `None` if it does not match at all.
+pub fn score(course: &Course, searched: &str) -> Option<u32> {
+    let key = searched.trim().to_lowercase();
+    if key.is_empty() {
+        return Some(0);
+    }
+
+    let is_origin = |word: &str| word.parse::<Origin>() == Ok(course.origin);
+    let after_origin = key
+        .split_once(' ')
+        .filter(|(origin, _)| is_origin(origin))
+        .and_then(|(_, rest)| phrase_score(course, rest.trim()));
+
+    [
+        coord_score(course, &key),
+        phrase_score(course, &key),
+        after_origin,
+        is_origin(&key).then_some(ORIGIN_SCORE),
+        words_score(course, &key),
+    ]
+    .into_iter()
+    .flatten()
+    .max()
+}
+
+fn coord_score(course: &Course, key: &str) -> Option<u32> {
+    let coord = course.coord.to_string();
+    if key == coord {
+        Some(MAX_SCORE)
+    } else if key.contains('-') && coord.starts_with(&format!("{key}-")) {
+        Some(CUP_SCORE)
+    } else {
+        None
+    }
+}
+
+// Score the search as a whole against the name and aliases
+fn phrase_score(course: &Course, key: &str) -> Option<u32> {
+    names(course)
+        .filter_map(|name| {
+            let name = name.to_lowercase();
+            if name == key {
+                Some(MAX_SCORE)
+            } else if name.starts_with(key) {
+                Some(STRONG_MATCH)
+            } else if name.contains(key) {
+                Some(PART_SCORE)
+            } else {
+                None
+            }
+        })
+        .max()
+}
+
+// Score the search word by word against the words of the name and aliases, and the origin
+fn words_score(course: &Course, key: &str) -> Option<u32> {
+    let searched = words(key);
+    let targets: Vec<Vec<char>> = names(course)
+        .flat_map(words)
+        .map(|w| w.chars().collect())
+        .collect();
+
+    let points: Vec<u32> = searched
+        .iter()
+        .map(|word| {
+            if word.parse::<Origin>() == Ok(course.origin) {
+                return WORD_ORIGIN;
+            }
+            let word: Vec<char> = word.chars().collect();
+            targets
+                .iter()
+                .filter_map(|target| word_points(&word, target))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let total = u32::try_from(points.len()).ok()?;
+    let missed = points.iter().filter(|&&p| p == 0).count();
+    if total == 0 || missed > usize::from(total >= 3) {
+        return None;
+    }
+    Some(WORDS_SCORE * points.iter().sum::<u32>() / (WORD_WHOLE * total))
+}
+
+fn word_points(word: &[char], target: &[char]) -> Option<u32> {
+    if word == target {
+        return Some(WORD_WHOLE);
+    }
+    if target.starts_with(word) {
+        return Some(WORD_START);
+    }
+
+    // A typo may be anywhere in the word, or the word may be the start of the target with a typo
+    let allowed = typos_allowed(word.len());
+    let start = &target[..word.len().min(target.len())];
+    let typos = edit_distance(word, target).min(edit_distance(word, start));
+    (typos <= allowed && allowed > 0)
+        .then(|| WORD_TYPO.saturating_sub(u32::try_from(typos).unwrap_or(u32::MAX)))
+}
+
+// Short words are often abbreviations, where a typo makes another abbreviation
+fn typos_allowed(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+fn names(course: &Course) -> impl Iterator<Item = &str> {
+    std::iter::once(course.name.as_str()).chain(course.aliases.iter().map(String::as_str))
+}
+
+// Split text into lowercase words, dropping possessives and punctuation, e.g. "Bowser's" becomes
+// "bowser" and "Sky-High" becomes "sky" and "high"
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-')
+        .map(|word| {
+            let word = word.strip_suffix("'s").unwrap_or(word);
+            word.chars().filter(|c| c.is_alphanumeric()).collect()
+        })
+        .filter(|word: &String| !word.is_empty())
+        .collect()
+}
+
+// Number of insertions, deletions, substitutions and swaps of neighbouring characters needed to
+// turn one word into the other
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![(0..=b.len()).collect::<Vec<usize>>()];
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (rows[i - 1][j] + 1)
+                .min(row[j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(rows[i - 2][j - 2] + 1);
+            }
+        }
+        rows.push(row);
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::courses::game::GameDefinition;
+
+    fn courses() -> &'static [Course] {
+        &GameDefinition::builtin().courses
+    }
+
+    fn find(searched: &str) -> Vec<SearchMatch> {
+        search(courses(), searched)
+    }
+
+    fn name(id: CourseId) -> String {
+        let course = courses().iter().find(|c| c.id == id).unwrap();
+        format!("{} {}", course.origin, course.name)
+    }
+
+    #[test]
+    fn typos_are_tolerated() {
+        let matches = find("coconot mall");
+        assert_eq!(name(matches[0].course), "Wii Coconut Mall");
+
+        let matches = find("dolphni shoals");
+        assert_eq!(name(matches[0].course), "MK8 Dolphin Shoals");
+        assert!(find("cmx").is_empty(), "abbreviations allow no typos");
+    }
+
+    #[test]
+    fn words_match_by_prefix() {
+        let matches = find("bows cast");
+        let found: Vec<String> = matches.iter().map(|m| name(m.course)).collect();
+        assert_eq!(
+            found[..2],
+            ["MK8 Bowser's Castle", "SNES Bowser's Castle 3"]
+        );
+        assert_eq!(strong_match(&matches, "bows cast"), None);
+
+        // A whole name is still not taken while another name starts with it
+        let matches = find("bowser's castle");
+        assert_eq!(name(matches[0].course), "MK8 Bowser's Castle");
+        assert_eq!(matches[0].score, MAX_SCORE);
+        assert_eq!(name(matches[1].course), "SNES Bowser's Castle 3");
+        assert_eq!(strong_match(&matches, "bowser's castle"), None);
+
+        let matches = find("coconut");
+        assert_eq!(matches[0].score, STRONG_MATCH);
+        assert_eq!(strong_match(&matches, "coconut"), Some(CourseId(0)));
+    }
+
+    #[test]
+    fn origin_narrows_a_search() {
+        // "RR" is also the start of Rock Rock Mountain's alias. A whole alias ranks higher, but
+        // both are strong matches, so neither is taken without asking
+        let matches = find("3DS RR");
+        assert_eq!(name(matches[0].course), "3DS Rainbow Road");
+        assert_eq!(matches[0].score, MAX_SCORE);
+        assert_eq!(name(matches[1].course), "3DS Rock Rock Mountain");
+        assert!(matches[1].score >= STRONG_MATCH);
+        assert_eq!(strong_match(&matches, "3DS RR"), None);
+
+        let matches = find("n64 rr");
+        assert_eq!(name(matches[0].course), "N64 Rainbow Road");
+        assert!(matches[1].score >= STRONG_MATCH);
+        assert_eq!(strong_match(&matches, "n64 rr"), None);
+
+        let matches = find("RR");
+        assert_eq!(matches.iter().filter(|m| m.score == MAX_SCORE).count(), 5);
+        assert_eq!(strong_match(&matches, "RR"), None);
+    }
+
+    #[test]
+    fn coordinates_find_courses_and_cups() {
+        let matches = find("3-1-4");
+        assert_eq!(name(matches[0].course), "Wii Coconut Mall");
+        assert_eq!(matches[0].score, MAX_SCORE);
+        assert_eq!(strong_match(&matches, "3-1-4"), Some(CourseId(0)));
+
+        let cup = find("3-1");
+        assert_eq!(cup.len(), 4);
+        assert!(cup.iter().all(|m| m.score == CUP_SCORE));
+    }
+
+    #[test]
+    fn origins_are_never_strong_matches() {
+        let matches = find("ds");
+        assert_eq!(name(matches[0].course), "MK8 Dolphin Shoals");
+        assert_eq!(matches[0].score, MAX_SCORE);
+        assert_eq!(strong_match(&matches, "ds"), None);
+
+        let wii = find("Wii");
+        let count = courses().iter().filter(|c| c.origin == Origin::Wii).count();
+        assert_eq!(wii.len(), count);
+        assert!(wii.iter().all(|m| m.score == wii[0].score));
+        assert_eq!(strong_match(&wii, "Wii"), None);
+    }
+}
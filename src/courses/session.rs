@@ -0,0 +1,48 @@
+//! Module defining the `Session` struct, a record of a stretch of time a `CourseList` was used,
+//! e.g. one game night.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+/// Time without changes after which the next change starts a new session, in seconds.
+pub const SESSION_GAP: u64 = 2 * 60 * 60;
+
+/// A stretch of time a list was used. A session starts with a change to the list and lasts until
+/// the last change made less than `SESSION_GAP` after the one before it, even across restarts.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Session {
+    /// When the first change of the session was made, in seconds since the Unix epoch.
+    pub started: u64,
+    /// When the last change of the session was made, in seconds since the Unix epoch.
+    pub ended: u64,
+    /// Number of courses rolled during the session, including rolls that were later undone.
+    pub rolled: usize,
+}
+
+impl Session {
+    pub(crate) fn start(now: u64) -> Self {
+        Session {
+            started: now,
+            ended: now,
+            rolled: 0,
+        }
+    }
+
+    /// Get how long the session lasted, from its first change to its last.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.ended.saturating_sub(self.started))
+    }
+
+    // Whether a change made at `now` belongs to a new session
+    pub(crate) fn is_over(&self, now: u64) -> bool {
+        now.saturating_sub(self.ended) >= SESSION_GAP
+    }
+
+    // The current time in seconds since the Unix epoch
+    pub(crate) fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |t| t.as_secs())
+    }
+}
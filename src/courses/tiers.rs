@@ -0,0 +1,255 @@
+//! Module defining the strategies used to split a list of active courses into tiers.
+
+#![allow(clippy::result_unit_err)]
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+use super::course::{Course, CourseId, Origin, find_by_id};
+
+/// Strategy used to split the active courses into tiers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TierStrategy {
+    /// Split the active courses, ordered by rank, into N chunks of near-equal size. If the number
+    /// of active courses is not divisible by N, the first tiers each receive one extra course.
+    Even(usize),
+    /// Split the full rank range (1 to the lowest rank of any course, including retired courses,
+    /// which keep their ranks) into N equal bands. The size of each tier depends on which courses
+    /// are still active.
+    Percentile(usize),
+    /// One tier per `Origin` among the active courses.
+    Origin,
+    /// User-defined rank boundaries. Each boundary is the last rank of a tier, and every rank
+    /// after the final boundary forms one last tier. Boundaries must be strictly increasing.
+    Boundaries(Vec<usize>),
+}
+
+/// A group of active courses produced by a `TierStrategy`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tier {
+    /// Human-readable description of the tier, e.g. "Tier 1 (ranks 1-24)".
+    pub label: String,
+    /// Ids of the courses in this tier, ordered by rank.
+    pub courses: Vec<CourseId>,
+}
+
+impl Display for Tier {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.label.fmt(f)
+    }
+}
+
+impl TierStrategy {
+    /// Split `active`, a set of ids of courses in `courses`, into tiers. Tiers that would contain
+    /// no courses are omitted, as are ids not found in `courses`.
+    ///
+    /// # Errors
+    /// - If a chunk or band count of 0 is given.
+    /// - If `Even` is given more chunks than there are active courses.
+    /// - If `Percentile` is given more bands than there are ranks.
+    /// - If `Boundaries` are not strictly increasing.
+    pub fn split(
+        &self,
+        courses: &[Course],
+        active: impl Iterator<Item = CourseId>,
+    ) -> Result<Vec<Tier>, ()> {
+        let mut active: Vec<&Course> = active.filter_map(|id| find_by_id(courses, id)).collect();
+        active.sort_by_key(|c| c.rank);
+
+        let tiers = match self {
+            Self::Even(num_chunks) => split_even(&active, *num_chunks)?,
+            Self::Percentile(num_bands) => {
+                let total = courses.iter().map(|c| c.rank).max().unwrap_or(0);
+                if *num_bands == 0 || *num_bands > total {
+                    return Err(());
+                }
+
+                let bounds: Vec<usize> = (1..=*num_bands).map(|b| b * total / num_bands).collect();
+                split_by_bounds(&active, &bounds)
+            }
+            Self::Origin => split_by_origin(&active),
+            Self::Boundaries(bounds) => {
+                if !bounds.is_sorted_by(|a, b| a < b) {
+                    return Err(());
+                }
+
+                let mut bounds = bounds.clone();
+                bounds.push(usize::MAX);
+                split_by_bounds(&active, &bounds)
+            }
+        };
+
+        Ok(tiers)
+    }
+}
+
+fn split_even(active: &[&Course], num_chunks: usize) -> Result<Vec<Tier>, ()> {
+    if num_chunks == 0 || num_chunks > active.len() {
+        return Err(());
+    }
+
+    let base = active.len() / num_chunks;
+    let extra = active.len() % num_chunks;
+    let mut rest = active;
+    let mut res = Vec::with_capacity(num_chunks);
+
+    for n in 0..num_chunks {
+        let size = base + usize::from(n < extra);
+        let (chunk, tail) = rest.split_at(size);
+        rest = tail;
+
+        let first = chunk[0].rank;
+        let last = chunk[size - 1].rank;
+        res.push(Tier {
+            label: format!("Tier {} (ranks {first}-{last})", n + 1),
+            courses: chunk.iter().map(|c| c.id).collect(),
+        });
+    }
+
+    Ok(res)
+}
+
+/// `bounds` holds the last rank of each band and must be strictly increasing.
+fn split_by_bounds(active: &[&Course], bounds: &[usize]) -> Vec<Tier> {
+    let mut res = Vec::new();
+    let mut lower = 1;
+
+    for &upper in bounds {
+        let members: Vec<CourseId> = active
+            .iter()
+            .filter(|c| (lower..=upper).contains(&c.rank))
+            .map(|c| c.id)
+            .collect();
+
+        if !members.is_empty() {
+            let range = if upper == usize::MAX {
+                format!("ranks {lower}+")
+            } else {
+                format!("ranks {lower}-{upper}")
+            };
+
+            res.push(Tier {
+                label: format!("Tier {} ({range})", res.len() + 1),
+                courses: members,
+            });
+        }
+
+        lower = upper.saturating_add(1);
+    }
+
+    res
+}
+
+fn split_by_origin(active: &[&Course]) -> Vec<Tier> {
+    let mut groups: BTreeMap<Origin, Vec<CourseId>> = BTreeMap::new();
+    for course in active {
+        groups.entry(course.origin).or_default().push(course.id);
+    }
+
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(n, (origin, members))| Tier {
+            label: format!("Tier {} ({origin})", n + 1),
+            courses: members,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::courses::course::Coord;
+
+    // Courses ranked 1 to `count`, with ids matching their ranks and origins alternating
+    fn courses(count: u8) -> Vec<Course> {
+        (1..=count)
+            .map(|n| {
+                let origin = if n % 2 == 0 { Origin::Wii } else { Origin::MK8 };
+                let coord = Coord::new(1 + (n - 1) / 24, 1 + (n - 1) / 4 % 6, 1 + (n - 1) % 4);
+                Course::new(
+                    CourseId(n.into()),
+                    n.into(),
+                    coord,
+                    origin,
+                    &format!("Course {n}"),
+                )
+            })
+            .collect()
+    }
+
+    fn ids(tiers: &[Tier]) -> Vec<Vec<u32>> {
+        tiers
+            .iter()
+            .map(|t| t.courses.iter().map(|id| id.0).collect())
+            .collect()
+    }
+
+    #[test]
+    fn even_gives_extra_courses_to_the_first_tiers() {
+        let courses = courses(7);
+        let tiers = TierStrategy::Even(3)
+            .split(&courses, courses.iter().map(|c| c.id))
+            .unwrap();
+        assert_eq!(ids(&tiers), [vec![1, 2, 3], vec![4, 5], vec![6, 7]]);
+        assert_eq!(tiers[1].label, "Tier 2 (ranks 4-5)");
+
+        assert!(
+            TierStrategy::Even(0)
+                .split(&courses, courses.iter().map(|c| c.id))
+                .is_err()
+        );
+        assert!(
+            TierStrategy::Even(8)
+                .split(&courses, courses.iter().map(|c| c.id))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn percentile_bands_cover_every_rank() {
+        // Courses 7 and 8 are retired: inactive, but still holding the lowest ranks
+        let courses = courses(8);
+        let active = (1..=6).map(CourseId);
+        let tiers = TierStrategy::Percentile(2).split(&courses, active).unwrap();
+        assert_eq!(ids(&tiers), [vec![1, 2, 3, 4], vec![5, 6]]);
+        assert_eq!(tiers[1].label, "Tier 2 (ranks 5-8)");
+
+        // Empty bands are left out, and later tiers are numbered on
+        let active = [1, 2, 7].into_iter().map(CourseId);
+        let tiers = TierStrategy::Percentile(4).split(&courses, active).unwrap();
+        assert_eq!(ids(&tiers), [vec![1, 2], vec![7]]);
+        assert_eq!(tiers[1].label, "Tier 2 (ranks 7-8)");
+
+        assert!(
+            TierStrategy::Percentile(9)
+                .split(&courses, std::iter::empty())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn origin_and_boundaries() {
+        let courses = courses(5);
+        let tiers = TierStrategy::Origin
+            .split(&courses, courses.iter().map(|c| c.id))
+            .unwrap();
+        assert_eq!(ids(&tiers), [vec![2, 4], vec![1, 3, 5]]);
+        assert_eq!(tiers[0].label, "Tier 1 (Wii)");
+
+        let tiers = TierStrategy::Boundaries(vec![1, 3])
+            .split(&courses, courses.iter().map(|c| c.id))
+            .unwrap();
+        assert_eq!(ids(&tiers), [vec![1], vec![2, 3], vec![4, 5]]);
+        assert_eq!(tiers[2].label, "Tier 3 (ranks 4+)");
+
+        let unsorted = TierStrategy::Boundaries(vec![3, 3]);
+        assert!(
+            unsorted
+                .split(&courses, courses.iter().map(|c| c.id))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,705 @@
+//! Module defining the `Tournament` struct, which generates rounds of matches between players or
+//! teams and tracks who advances.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter, Write};
+
+use rand::seq::IteratorRandom;
+use serde::{Deserialize, Serialize};
+
+use super::course::{Course, CourseId, find_by_id};
+use super::results::{PointTable, RaceResult};
+
+/// Format of a tournament.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Every participant plays every other participant once.
+    RoundRobin,
+    /// Participants are eliminated after one loss.
+    SingleElimination,
+    /// Participants are eliminated after two losses. Participants play others with the same
+    /// number of losses where possible, so the last undefeated participant meets the survivors of
+    /// the one-loss bracket.
+    DoubleElimination,
+    /// A fixed number of rounds. Each round pairs participants with similar records who have not
+    /// played each other yet.
+    Swiss(usize),
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::RoundRobin => write!(f, "Round robin"),
+            Self::SingleElimination => write!(f, "Single elimination"),
+            Self::DoubleElimination => write!(f, "Double elimination"),
+            Self::Swiss(rounds) => write!(f, "Swiss ({rounds} rounds)"),
+        }
+    }
+}
+
+/// A player or team taking part in a tournament.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Participant {
+    /// Name of the player or team. Unique within a tournament.
+    pub name: String,
+    /// Names of the players making up the participant. A solo player's only member is themself.
+    pub members: Vec<String>,
+}
+
+/// A match between two participants, or a bye for one.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    /// Names of the participants in the match. A match with one participant is a bye, which that
+    /// participant wins automatically.
+    pub sides: Vec<String>,
+    /// Ids of the courses to race in the match.
+    pub courses: Vec<CourseId>,
+    /// Name of the winning participant, once decided.
+    pub winner: Option<String>,
+}
+
+/// Identifies a match of a tournament, so race results can be recorded for it.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchRef {
+    /// Number of the tournament. See `Tournament::number`.
+    pub tournament: usize,
+    /// Index of the round, starting at 0.
+    pub round: usize,
+    /// Index of the match within the round, starting at 0.
+    pub index: usize,
+}
+
+impl Match {
+    /// Whether the match is a bye.
+    #[inline]
+    pub fn is_bye(&self) -> bool {
+        self.sides.len() == 1
+    }
+}
+
+/// Errors that can occur while running a tournament.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TournamentError {
+    /// Fewer than two participants, or two participants share a name.
+    InvalidParticipants,
+    /// The previous round still has matches without a winner.
+    RoundInProgress,
+    /// The tournament has no more rounds to play.
+    Finished,
+    /// Not enough unused courses remain to fill the next round.
+    NotEnoughCourses,
+    /// The round or match does not exist, or is a bye.
+    NoSuchMatch,
+    /// The winner is not a participant in the match.
+    NotInMatch,
+    /// Recorded race results do not decide a winner for the match.
+    Undecided,
+    /// A later round has been generated, so the round's results and winners are final.
+    RoundClosed,
+    /// The course is not assigned to the match.
+    CourseNotInMatch,
+    /// The placements of a result are invalid. See `CourseList::record_result`.
+    InvalidResult,
+}
+
+impl Display for TournamentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidParticipants => {
+                write!(
+                    f,
+                    "a tournament needs at least two uniquely named participants"
+                )
+            }
+            Self::RoundInProgress => write!(f, "the current round has undecided matches"),
+            Self::Finished => write!(f, "the tournament is finished"),
+            Self::NotEnoughCourses => write!(f, "not enough unused courses for the next round"),
+            Self::NoSuchMatch => write!(f, "no such match"),
+            Self::NotInMatch => write!(f, "the winner must be a participant in the match"),
+            Self::Undecided => write!(f, "recorded results do not decide a winner"),
+            Self::RoundClosed => write!(f, "a later round has already been generated"),
+            Self::CourseNotInMatch => write!(f, "the course is not part of the match"),
+            Self::InvalidResult => write!(
+                f,
+                "placements must be unique, start at 1, and include at least one player"
+            ),
+        }
+    }
+}
+
+impl Error for TournamentError {}
+
+/// A tournament.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Tournament {
+    /// Number of the tournament, counting up from 1 within a `CourseList`, or 0 if it is not
+    /// part of one. Race results recorded for its matches refer to it by this number.
+    #[serde(default)]
+    pub number: usize,
+    /// Format of the tournament.
+    pub format: Format,
+    /// Participants, in seeding order.
+    pub participants: Vec<Participant>,
+    /// Number of courses to race in each match.
+    pub courses_per_match: usize,
+    /// Rounds generated so far, oldest first.
+    pub rounds: Vec<Vec<Match>>,
+}
+
+impl Tournament {
+    /// Create a tournament with no rounds.
+    ///
+    /// # Errors
+    /// - If there are fewer than two participants, or two participants share a name.
+    pub fn new(
+        format: Format,
+        participants: Vec<Participant>,
+        courses_per_match: usize,
+    ) -> Result<Self, TournamentError> {
+        let names: BTreeSet<&str> = participants.iter().map(|p| p.name.as_str()).collect();
+        if participants.len() < 2 || names.len() != participants.len() {
+            return Err(TournamentError::InvalidParticipants);
+        }
+
+        Ok(Tournament {
+            number: 0,
+            format,
+            participants,
+            courses_per_match,
+            rounds: Vec::new(),
+        })
+    }
+
+    /// Ids of every course assigned to a match so far.
+    pub fn used_courses(&self) -> BTreeSet<CourseId> {
+        self.matches()
+            .flat_map(|m| m.courses.iter().copied())
+            .collect()
+    }
+
+    /// Generate the next round, assigning each match courses drawn at random from `courses`,
+    /// a set of course ids, skipping any already used in the tournament.
+    ///
+    /// # Errors
+    /// - If the current round still has undecided matches.
+    /// - If the tournament is finished.
+    /// - If there are not enough unused courses for every match in the round.
+    pub fn next_round(
+        &mut self,
+        courses: impl Iterator<Item = CourseId>,
+    ) -> Result<&[Match], TournamentError> {
+        if self.matches().any(|m| m.winner.is_none()) {
+            return Err(TournamentError::RoundInProgress);
+        }
+        if self.is_finished() {
+            return Err(TournamentError::Finished);
+        }
+
+        let pairings = match self.format {
+            Format::RoundRobin => self.round_robin_pairings(),
+            Format::SingleElimination => self.elimination_pairings(1),
+            Format::DoubleElimination => self.elimination_pairings(2),
+            Format::Swiss(_) => self.swiss_pairings(),
+        };
+
+        let used = self.used_courses();
+        let num_playing = pairings.iter().filter(|p| p.len() > 1).count();
+        let mut fresh = courses
+            .filter(|c| !used.contains(c))
+            .choose_multiple(&mut rand::rng(), num_playing * self.courses_per_match)
+            .into_iter();
+        if fresh.len() < num_playing * self.courses_per_match {
+            return Err(TournamentError::NotEnoughCourses);
+        }
+
+        let round = pairings
+            .into_iter()
+            .map(|sides| {
+                let (courses, winner) = if sides.len() > 1 {
+                    let courses = fresh.by_ref().take(self.courses_per_match).collect();
+                    (courses, None)
+                } else {
+                    (Vec::new(), Some(sides[0].clone()))
+                };
+
+                Match {
+                    sides,
+                    courses,
+                    winner,
+                }
+            })
+            .collect();
+
+        self.rounds.push(round);
+        Ok(self.rounds.last().map(Vec::as_slice).unwrap_or_default())
+    }
+
+    /// Record the winner of a match. Both indices start at 0. Winners can be changed until the
+    /// next round is generated.
+    ///
+    /// # Errors
+    /// - If the match does not exist or is a bye.
+    /// - If a later round has been generated.
+    /// - If `winner` is not a participant in the match.
+    pub fn record_winner(
+        &mut self,
+        round_i: usize,
+        match_i: usize,
+        winner: &str,
+    ) -> Result<(), TournamentError> {
+        self.check_open(round_i, match_i)?;
+        let m = &mut self.rounds[round_i][match_i];
+
+        let side = m
+            .sides
+            .iter()
+            .find(|s| s.eq_ignore_ascii_case(winner))
+            .ok_or(TournamentError::NotInMatch)?;
+        m.winner = Some(side.clone());
+        Ok(())
+    }
+
+    /// Check that a match exists, is not a bye, and is in the latest round, so its results and
+    /// winner can still change.
+    ///
+    /// # Errors
+    /// - If the match does not exist or is a bye.
+    /// - If a later round has been generated.
+    pub fn check_open(&self, round_i: usize, match_i: usize) -> Result<&Match, TournamentError> {
+        let m = self
+            .rounds
+            .get(round_i)
+            .and_then(|r| r.get(match_i))
+            .filter(|m| !m.is_bye())
+            .ok_or(TournamentError::NoSuchMatch)?;
+        if round_i + 1 < self.rounds.len() {
+            return Err(TournamentError::RoundClosed);
+        }
+        Ok(m)
+    }
+
+    /// Get the reference race results use to refer to a match. Both indices start at 0.
+    pub fn match_ref(&self, round_i: usize, match_i: usize) -> MatchRef {
+        MatchRef {
+            tournament: self.number,
+            round: round_i,
+            index: match_i,
+        }
+    }
+
+    /// Work out the winner of a match from the race results recorded for it, by summing the points
+    /// each side's members scored on the match's courses. Both indices start at 0. Only the
+    /// latest result for each course counts, and results recorded outside the match are ignored.
+    /// Returns `None` if the match does not exist, if no results were recorded for it, or if the
+    /// sides tied.
+    pub fn winner_from_results(
+        &self,
+        round_i: usize,
+        match_i: usize,
+        results: &[RaceResult],
+        table: &PointTable,
+    ) -> Option<String> {
+        let m = self.rounds.get(round_i)?.get(match_i)?;
+        let match_ref = self.match_ref(round_i, match_i);
+        let latest: Vec<&RaceResult> = m
+            .courses
+            .iter()
+            .filter_map(|&c| {
+                results
+                    .iter()
+                    .rev()
+                    .find(|r| r.course == c && r.tournament_match == Some(match_ref))
+            })
+            .collect();
+        if latest.is_empty() {
+            return None;
+        }
+
+        let mut scores: Vec<(&String, u32)> = m
+            .sides
+            .iter()
+            .map(|side| {
+                let members = self.members(side);
+                let points = latest
+                    .iter()
+                    .flat_map(|r| &r.placements)
+                    .filter(|(player, _)| members.iter().any(|m| m.eq_ignore_ascii_case(player)))
+                    .map(|(_, &placement)| table.points(placement))
+                    .sum();
+                (side, points)
+            })
+            .collect();
+
+        scores.sort_by_key(|&(_, points)| Reverse(points));
+        match scores.as_slice() {
+            [(first, a), (_, b), ..] if a > b => Some((*first).clone()),
+            [(first, _)] => Some((*first).clone()),
+            _ => None,
+        }
+    }
+
+    /// Number of wins and losses of every participant, in seeding order. Byes count as wins.
+    pub fn records(&self) -> Vec<(&str, usize, usize)> {
+        self.participants
+            .iter()
+            .map(|p| {
+                let (wins, losses) = self.record(&p.name);
+                (p.name.as_str(), wins, losses)
+            })
+            .collect()
+    }
+
+    /// Whether every round has been played.
+    pub fn is_finished(&self) -> bool {
+        if self.matches().any(|m| m.winner.is_none()) {
+            return false;
+        }
+
+        match self.format {
+            Format::RoundRobin => {
+                let n = self.participants.len();
+                self.rounds.len() >= n - 1 + n % 2
+            }
+            Format::SingleElimination => self.alive(1).len() <= 1,
+            Format::DoubleElimination => self.alive(2).len() <= 1,
+            Format::Swiss(rounds) => self.rounds.len() >= rounds,
+        }
+    }
+
+    /// Get the winner of the tournament, once it is finished. In round robin and Swiss formats,
+    /// this is the participant with the most wins, with ties going to the higher seed.
+    pub fn champion(&self) -> Option<&str> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        match self.format {
+            Format::SingleElimination => self.alive(1).first().copied(),
+            Format::DoubleElimination => self.alive(2).first().copied(),
+            Format::RoundRobin | Format::Swiss(_) => self
+                .records()
+                .into_iter()
+                .rev()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)))
+                .map(|(name, _, _)| name),
+        }
+    }
+
+    /// Describe the tournament as plain text, using `courses` to name assigned courses.
+    pub fn to_text(&self, courses: &[Course]) -> String {
+        let mut res = String::new();
+        // Writing to a String cannot fail, so the results are ignored
+        let _ = writeln!(res, "{} tournament", self.format);
+
+        for (round_i, round) in self.rounds.iter().enumerate() {
+            let _ = writeln!(res, "\nRound {}", round_i + 1);
+            for (match_i, m) in round.iter().enumerate() {
+                let winner = m.winner.as_deref().unwrap_or("undecided");
+                if m.is_bye() {
+                    let _ = writeln!(res, "  {}. {} (bye)", match_i + 1, m.sides[0]);
+                    continue;
+                }
+
+                let _ = writeln!(
+                    res,
+                    "  {}. {} - winner: {winner}",
+                    match_i + 1,
+                    m.sides.join(" vs "),
+                );
+                for course in m.courses.iter().filter_map(|&c| find_by_id(courses, c)) {
+                    let _ = writeln!(res, "       {course}");
+                }
+            }
+        }
+
+        let _ = writeln!(res, "\nRecords");
+        for (name, wins, losses) in self.records() {
+            let _ = writeln!(res, "  {name}: {wins}-{losses}");
+        }
+        if let Some(champion) = self.champion() {
+            let _ = writeln!(res, "\nChampion: {champion}");
+        }
+
+        res
+    }
+
+    fn matches(&self) -> impl Iterator<Item = &Match> {
+        self.rounds.iter().flatten()
+    }
+
+    fn members(&self, name: &str) -> &[String] {
+        self.participants
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| p.members.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn record(&self, name: &str) -> (usize, usize) {
+        self.matches()
+            .filter(|m| m.sides.iter().any(|s| s == name))
+            .filter_map(|m| m.winner.as_deref())
+            .fold((0, 0), |(wins, losses), winner| {
+                if winner == name {
+                    (wins + 1, losses)
+                } else {
+                    (wins, losses + 1)
+                }
+            })
+    }
+
+    fn byes(&self, name: &str) -> usize {
+        self.matches()
+            .filter(|m| m.is_bye() && m.sides[0] == name)
+            .count()
+    }
+
+    fn have_played(&self, a: &str, b: &str) -> bool {
+        self.matches()
+            .any(|m| m.sides.iter().any(|s| s == a) && m.sides.iter().any(|s| s == b))
+    }
+
+    // Participants with fewer than `lives` losses, in seeding order
+    fn alive(&self, lives: usize) -> Vec<&str> {
+        self.records()
+            .into_iter()
+            .filter(|&(_, _, losses)| losses < lives)
+            .map(|(name, _, _)| name)
+            .collect()
+    }
+
+    // Remove and return the highest seed in `group` with the fewest byes
+    fn take_bye_candidate(&self, group: &mut Vec<&str>) -> Option<String> {
+        let pos = (0..group.len()).min_by_key(|&i| self.byes(group[i]))?;
+        Some(group.remove(pos).to_owned())
+    }
+
+    // Circle method: the first participant stays put while the others rotate one step per round
+    fn round_robin_pairings(&self) -> Vec<Vec<String>> {
+        let mut seats: Vec<Option<&str>> = self
+            .participants
+            .iter()
+            .map(|p| Some(p.name.as_str()))
+            .collect();
+        if seats.len() % 2 == 1 {
+            seats.push(None);
+        }
+
+        let n = seats.len();
+        seats[1..].rotate_right(self.rounds.len() % (n - 1));
+
+        (0..n / 2)
+            .filter_map(|i| match (seats[i], seats[n - 1 - i]) {
+                (Some(a), Some(b)) => Some(vec![a.to_owned(), b.to_owned()]),
+                (Some(a), None) | (None, Some(a)) => Some(vec![a.to_owned()]),
+                (None, None) => None,
+            })
+            .collect()
+    }
+
+    // Pair participants with the same number of losses, top seed against bottom seed. When a group
+    // has an odd size, one participant waits for an opponent from the next group down, or gets a
+    // bye if there is none. This also produces the grand final of double elimination.
+    fn elimination_pairings(&self, lives: usize) -> Vec<Vec<String>> {
+        let mut groups: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+        for (name, _, losses) in self.records() {
+            if losses < lives {
+                groups.entry(losses).or_default().push(name);
+            }
+        }
+
+        let mut res = Vec::new();
+        let mut carried: Option<String> = None;
+
+        for (_, mut group) in groups {
+            let mut names: Vec<String> = Vec::new();
+            if (group.len() + usize::from(carried.is_some())) % 2 == 1 {
+                // A participant already waiting keeps waiting, so the group can whittle itself
+                // down to one opponent for them
+                if carried.is_none() {
+                    carried = self.take_bye_candidate(&mut group);
+                }
+            } else if let Some(name) = carried.take() {
+                names.push(name);
+            }
+            names.extend(group.into_iter().map(str::to_owned));
+
+            while names.len() >= 2 {
+                let last = names.pop().unwrap_or_default();
+                let first = names.remove(0);
+                res.push(vec![first, last]);
+            }
+        }
+
+        if let Some(name) = carried {
+            res.push(vec![name]);
+        }
+        res
+    }
+
+    // Pair participants by number of wins, each against the next best participant they have not
+    // played yet. The lowest ranked participant with the fewest byes gets a bye.
+    fn swiss_pairings(&self) -> Vec<Vec<String>> {
+        let mut ranked: Vec<(&str, usize, usize)> = self.records();
+        ranked.sort_by_key(|&(_, wins, _)| Reverse(wins));
+        let mut names: Vec<&str> = ranked.into_iter().map(|(name, _, _)| name).collect();
+
+        let mut bye = None;
+        if names.len() % 2 == 1 {
+            names.reverse();
+            bye = self.take_bye_candidate(&mut names);
+            names.reverse();
+        }
+
+        let mut res = Vec::new();
+        while !names.is_empty() {
+            let first = names.remove(0);
+            let pos = names
+                .iter()
+                .position(|&other| !self.have_played(first, other))
+                .unwrap_or(0);
+            let second = names.remove(pos);
+            res.push(vec![first.to_owned(), second.to_owned()]);
+        }
+
+        res.extend(bye.map(|name| vec![name]));
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn players(count: usize) -> Vec<Participant> {
+        (1..=count)
+            .map(|i| Participant {
+                name: format!("P{i}"),
+                members: vec![format!("P{i}")],
+            })
+            .collect()
+    }
+
+    fn seed(name: &str) -> usize {
+        name[1..].parse().unwrap()
+    }
+
+    // Generate a round and let the higher seed win every match
+    fn play_round(tournament: &mut Tournament) {
+        let round_i = tournament.rounds.len();
+        let round = tournament
+            .next_round((0..200).map(CourseId))
+            .unwrap()
+            .to_vec();
+        for (match_i, m) in round.iter().enumerate().filter(|(_, m)| !m.is_bye()) {
+            let best = m.sides.iter().min_by_key(|s| seed(s)).unwrap();
+            tournament.record_winner(round_i, match_i, best).unwrap();
+        }
+    }
+
+    #[test]
+    fn odd_round_robin_gives_everyone_one_bye() {
+        let mut tournament = Tournament::new(Format::RoundRobin, players(5), 1).unwrap();
+        while !tournament.is_finished() {
+            play_round(&mut tournament);
+        }
+
+        assert_eq!(tournament.rounds.len(), 5);
+        for p in &tournament.participants {
+            assert_eq!(tournament.byes(&p.name), 1);
+            for other in tournament.participants.iter().filter(|o| o.name != p.name) {
+                assert!(tournament.have_played(&p.name, &other.name));
+            }
+        }
+        assert_eq!(tournament.champion(), Some("P1"));
+        assert_eq!(
+            tournament.next_round((0..200).map(CourseId)).unwrap_err(),
+            TournamentError::Finished
+        );
+    }
+
+    #[test]
+    fn swiss_avoids_rematches() {
+        for count in [7, 8] {
+            let mut tournament = Tournament::new(Format::Swiss(3), players(count), 2).unwrap();
+            while !tournament.is_finished() {
+                play_round(&mut tournament);
+            }
+
+            let mut pairs = BTreeSet::new();
+            for m in tournament.matches().filter(|m| !m.is_bye()) {
+                let mut pair = m.sides.clone();
+                pair.sort();
+                assert!(pairs.insert(pair), "rematch in {:?}", tournament.rounds);
+            }
+            for p in &tournament.participants {
+                assert!(tournament.byes(&p.name) <= 1);
+            }
+            assert_eq!(tournament.used_courses().len(), pairs.len() * 2);
+            assert_eq!(tournament.champion(), Some("P1"));
+        }
+    }
+
+    #[test]
+    fn elimination_runs_to_one_winner() {
+        for (format, lives) in [
+            (Format::SingleElimination, 1),
+            (Format::DoubleElimination, 2),
+        ] {
+            for count in 2..=9 {
+                let mut tournament = Tournament::new(format, players(count), 1).unwrap();
+                while !tournament.is_finished() {
+                    play_round(&mut tournament);
+                    assert!(tournament.rounds.len() <= 2 * count, "{format} never ended");
+                }
+
+                assert_eq!(tournament.alive(lives), ["P1"]);
+                assert_eq!(tournament.champion(), Some("P1"));
+                // Everyone but the champion was knocked out
+                for (name, _, losses) in tournament.records().into_iter().skip(1) {
+                    assert_eq!(losses, lives, "{name} in {format} with {count} players");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rounds_wait_for_winners() {
+        let mut tournament = Tournament::new(Format::SingleElimination, players(3), 1).unwrap();
+        let round = tournament.next_round((0..1).map(CourseId)).unwrap();
+        assert_eq!(round.len(), 2);
+        assert!(round[1].is_bye());
+        assert_eq!(round[1].winner.as_deref(), Some("P1"));
+
+        assert_eq!(
+            tournament.next_round((0..1).map(CourseId)).unwrap_err(),
+            TournamentError::RoundInProgress
+        );
+        assert_eq!(
+            tournament.record_winner(0, 0, "P1").unwrap_err(),
+            TournamentError::NotInMatch
+        );
+        assert_eq!(
+            tournament.record_winner(0, 1, "P1").unwrap_err(),
+            TournamentError::NoSuchMatch
+        );
+        tournament.record_winner(0, 0, "p3").unwrap();
+        assert_eq!(
+            tournament.next_round((0..1).map(CourseId)).unwrap_err(),
+            TournamentError::NotEnoughCourses
+        );
+
+        tournament.next_round((0..2).map(CourseId)).unwrap();
+        assert_eq!(
+            tournament.record_winner(0, 0, "P2").unwrap_err(),
+            TournamentError::RoundClosed
+        );
+        assert!(
+            Tournament::new(Format::RoundRobin, players(1), 1).is_err()
+                && Tournament::new(Format::RoundRobin, [players(1), players(1)].concat(), 1)
+                    .is_err()
+        );
+    }
+}
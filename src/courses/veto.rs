@@ -0,0 +1,81 @@
+//! Module defining the `VetoPhase` struct, which tracks a pick/ban phase before a prix.
+
+use serde::{Deserialize, Serialize};
+
+use super::course::CourseId;
+
+/// A pick/ban phase: a set of candidate courses is drawn, then each player in turn strikes one.
+/// The surviving candidates are raced as a prix.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct VetoPhase {
+    /// Candidate courses that have not been struck.
+    pub candidates: Vec<CourseId>,
+    /// Strikes made so far, in order, as pairs of player index and course id.
+    pub strikes: Vec<(usize, CourseId)>,
+    /// Indices of the players striking, in turn order. Each strikes exactly one course.
+    pub order: Vec<usize>,
+}
+
+impl VetoPhase {
+    /// Get the index of the player whose turn it is to strike, or `None` if every player has
+    /// struck a course.
+    pub fn striker(&self) -> Option<usize> {
+        self.order.get(self.strikes.len()).copied()
+    }
+
+    /// Whether every player has struck a course.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.strikes.len() >= self.order.len()
+    }
+
+    /// Strike a candidate. Does nothing if the course is not a candidate.
+    pub(crate) fn apply_strike(&mut self, player_i: usize, course: CourseId) {
+        if let Some(pos) = self.candidates.iter().position(|&c| c == course) {
+            self.candidates.remove(pos);
+            self.strikes.push((player_i, course));
+        }
+    }
+
+    /// Take back the most recent strike if it was of the given course.
+    pub(crate) fn undo_strike(&mut self, course: CourseId) {
+        if self.strikes.last().is_some_and(|&(_, c)| c == course) {
+            self.strikes.pop();
+            self.candidates.push(course);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn players_strike_in_turn() {
+        let mut veto = VetoPhase {
+            candidates: [1, 2, 3, 4].map(CourseId).to_vec(),
+            strikes: Vec::new(),
+            order: vec![2, 0],
+        };
+        assert_eq!(veto.striker(), Some(2));
+
+        veto.apply_strike(2, CourseId(9));
+        assert_eq!(
+            veto.striker(),
+            Some(2),
+            "courses that are not candidates are ignored"
+        );
+        veto.apply_strike(2, CourseId(3));
+        veto.apply_strike(0, CourseId(1));
+        assert!(veto.is_finished());
+        assert_eq!(veto.striker(), None);
+        assert_eq!(veto.candidates, [2, 4].map(CourseId));
+
+        // Only the latest strike can be taken back
+        veto.undo_strike(CourseId(3));
+        assert_eq!(veto.strikes.len(), 2);
+        veto.undo_strike(CourseId(1));
+        assert_eq!(veto.striker(), Some(0));
+        assert_eq!(veto.strikes, [(2, CourseId(3))]);
+    }
+}
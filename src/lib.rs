@@ -3,10 +3,19 @@
 #![warn(missing_docs, clippy::all, clippy::pedantic)]
 #![allow(clippy::must_use_candidate)]
 
-use std::path::PathBuf;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
 
+use tempfile::NamedTempFile;
+
+pub mod autosave;
 pub mod courses;
+pub mod lock;
+pub mod overlay;
+pub mod report;
+pub mod saves;
 
 /// Path to the directory holding serialized `CourseList`s, called 'saves.'
 /// Because each platform has its own data directory, this can only be determined at runtime. As
@@ -17,3 +26,24 @@ pub static SAVES_DIR: LazyLock<PathBuf> = LazyLock::new(|| {
     saves_dir.push("mk8d-random");
     saves_dir
 });
+
+/// Write `data` to `path` atomically: the data is written to a temporary file in the same
+/// directory, which then replaces `path`. Readers never see a partially written file. If `path`
+/// already exists, its permissions are kept.
+///
+/// # Errors
+/// - If the temporary file cannot be created or written.
+/// - If the temporary file cannot be moved to `path`.
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut file = NamedTempFile::new_in(dir)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        file.as_file().set_permissions(metadata.permissions())?;
+    }
+    file.write_all(data)?;
+    file.persist(path)?;
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! Module defining the `SaveLock` struct, an advisory lock that keeps two sessions from using the
+//! same save at once.
+//!
+//! A save `name.json` is locked through a lockfile `name.json.lock` next to it, holding the ID of
+//! the process using the save. The lock itself is taken by the operating system on the lockfile,
+//! so it is released when the process exits, even if it crashes. A lockfile that exists but is
+//! not locked was left behind by a session that is gone, and is simply taken over. The lockfile
+//! of a save that does not exist when its lock is released is removed, so failed attempts to
+//! create, rename or delete a save leave nothing behind.
+
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Extension added to a save's path to get its lockfile's path.
+pub const LOCK_EXTENSION: &str = "lock";
+
+/// A held lock on a save file. The lock is released when this is dropped.
+#[derive(Debug)]
+pub struct SaveLock {
+    file: File,
+    save_path: PathBuf,
+}
+
+impl SaveLock {
+    /// Get the path of the lockfile for the save at `save_path`.
+    pub fn lock_path(save_path: &Path) -> PathBuf {
+        let mut path = save_path.as_os_str().to_owned();
+        path.push(".");
+        path.push(LOCK_EXTENSION);
+        PathBuf::from(path)
+    }
+
+    /// Lock the save at `save_path`, which does not need to exist yet.
+    ///
+    /// # Errors
+    /// - If another session holds the lock. The error is of kind `ErrorKind::ResourceBusy`.
+    /// - If the lockfile cannot be opened, locked or written.
+    pub fn acquire(save_path: &Path) -> io::Result<Self> {
+        let lock_path = Self::lock_path(save_path);
+        let mut file = loop {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&lock_path)?;
+
+            match file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => {
+                    let mut owner = String::new();
+                    file.read_to_string(&mut owner)?;
+                    let owner = match owner.trim() {
+                        "" => String::new(),
+                        pid => format!(" (process {pid})"),
+                    };
+                    return Err(io::Error::new(
+                        io::ErrorKind::ResourceBusy,
+                        format!(
+                            "{} is in use by another session{owner}",
+                            save_path.display()
+                        ),
+                    ));
+                }
+                Err(TryLockError::Error(e)) => return Err(e),
+            }
+
+            // The session that held the lock may have removed the lockfile before releasing it,
+            // in which case the lock is on a file nobody else will look at
+            if is_current(&file, &lock_path)? {
+                break file;
+            }
+        };
+
+        // Anything already in the file was left by a session that is gone
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+
+        Ok(SaveLock {
+            file,
+            save_path: save_path.to_owned(),
+        })
+    }
+
+    /// Check whether another session holds the lock on the save at `save_path`, without taking
+    /// it. This is only a snapshot: the lock may be taken or released right after.
+    ///
+    /// # Errors
+    /// - If the lockfile exists but cannot be opened or checked.
+    pub fn is_locked(save_path: &Path) -> io::Result<bool> {
+        let file = match File::open(Self::lock_path(save_path)) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+
+        match file.try_lock_shared() {
+            Ok(()) => {
+                file.unlock()?;
+                Ok(false)
+            }
+            Err(TryLockError::WouldBlock) => Ok(true),
+            Err(TryLockError::Error(e)) => Err(e),
+        }
+    }
+
+    /// Get the path of the locked save.
+    #[inline]
+    pub fn save_path(&self) -> &Path {
+        &self.save_path
+    }
+}
+
+impl Drop for SaveLock {
+    fn drop(&mut self) {
+        // Errors are ignored: the lock is released when the file is closed regardless. The
+        // lockfile is removed while the lock is still held, so no other session can be using it
+        if !self.save_path.try_exists().unwrap_or(true) {
+            let _ = fs::remove_file(Self::lock_path(&self.save_path));
+        }
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+// Check whether `file` is still the file at `path`, rather than one that has been removed
+#[cfg(unix)]
+fn is_current(file: &File, path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+
+    let held = file.metadata()?;
+    match fs::metadata(path) {
+        Ok(current) => Ok(held.dev() == current.dev() && held.ino() == current.ino()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+// Open files cannot be removed elsewhere, so the file at `path` is the one held while it exists
+#[cfg(not(unix))]
+fn is_current(_file: &File, path: &Path) -> io::Result<bool> {
+    path.try_exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_one_session_holds_a_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("save.json");
+
+        let lock = SaveLock::acquire(&save).unwrap();
+        assert_eq!(lock.save_path(), save);
+        assert!(SaveLock::is_locked(&save).unwrap());
+        let pid = std::process::id().to_string();
+        assert_eq!(fs::read_to_string(SaveLock::lock_path(&save)).unwrap(), pid);
+
+        let err = SaveLock::acquire(&save).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ResourceBusy);
+        assert!(err.to_string().contains(&pid));
+
+        drop(lock);
+        assert!(!SaveLock::is_locked(&save).unwrap());
+        SaveLock::acquire(&save).unwrap();
+    }
+
+    #[test]
+    fn lockfiles_only_outlive_existing_saves() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("save.json");
+        let lock_path = SaveLock::lock_path(&save);
+        assert_eq!(lock_path, dir.path().join("save.json.lock"));
+
+        drop(SaveLock::acquire(&save).unwrap());
+        assert!(!lock_path.exists());
+        assert!(!SaveLock::is_locked(&save).unwrap());
+
+        fs::write(&save, "{}").unwrap();
+        drop(SaveLock::acquire(&save).unwrap());
+        assert_eq!(fs::read_to_string(&lock_path).unwrap(), "");
+    }
+
+    #[test]
+    fn stale_lockfiles_are_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let save = dir.path().join("save.json");
+        fs::write(SaveLock::lock_path(&save), "999999999").unwrap();
+        assert!(!SaveLock::is_locked(&save).unwrap());
+
+        let _lock = SaveLock::acquire(&save).unwrap();
+        let owner = fs::read_to_string(SaveLock::lock_path(&save)).unwrap();
+        assert_eq!(owner, std::process::id().to_string());
+    }
+}
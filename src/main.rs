@@ -5,32 +5,77 @@
 #![allow(clippy::needless_pass_by_value)]
 #![allow(clippy::unit_arg)]
 
+mod broadcaster;
+mod cli;
 mod repl;
+mod server;
 mod tui;
 
-use std::fs::{DirEntry, create_dir};
+use std::fs::create_dir;
+use std::path::PathBuf;
 
 use anyhow::Context;
 use clap::{self, Parser, ValueEnum};
 
 use mk8d_random::SAVES_DIR;
+use mk8d_random::autosave::AutosavePolicy;
+use mk8d_random::courses::course_list::CourseList;
+use mk8d_random::courses::game::{self, GameDefinition};
+use mk8d_random::overlay::OverlayWriter;
+use mk8d_random::saves;
 
+use cli::Command;
 use repl::Repl;
+use server::Server;
 use tui::tui::Tui;
 
-const MK8D_DEFAULT_SAVE_JSON: &str = include_str!("../data/mk8d_default_save.json");
-
 #[derive(Debug, Clone, ValueEnum)]
 enum Mode {
     Repl,
     Tui,
+    Serve,
 }
 
 #[derive(Debug, Parser)]
 #[command(name = "mk8d-random", author = "UserOfNames", version, about)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(short, value_enum, default_value_t = Mode::Repl, help = "Mode for the UI")]
     mode: Mode,
+
+    #[arg(
+        short,
+        default_value_t = 8080,
+        help = "Port to listen on in serve mode"
+    )]
+    port: u16,
+
+    #[arg(
+        short,
+        help = "Port to stream events on over WebSocket in serve mode. Disabled if not given"
+    )]
+    ws_port: Option<u16>,
+
+    #[arg(
+        short,
+        help = "Save to load in serve mode, e.g. 'save.json'. Defaults to mk8d"
+    )]
+    save: Option<PathBuf>,
+
+    #[arg(
+        short,
+        help = "Directory to write stream overlay files to on every change"
+    )]
+    overlay: Option<PathBuf>,
+
+    #[arg(
+        short,
+        help = "Save automatically: 'change' after every change, N after every N changes, or \
+            e.g. '30s' at most every 30 seconds"
+    )]
+    autosave: Option<AutosavePolicy>,
 }
 
 fn main() -> anyhow::Result<()> {
@@ -40,10 +85,17 @@ fn main() -> anyhow::Result<()> {
         create_dir(&*SAVES_DIR).context("Creating save directory")?;
     }
 
-    let saves: Result<Vec<DirEntry>, _> = std::fs::read_dir(&*SAVES_DIR)
-        .context(format!("Accessing save directory {SAVES_DIR:?}"))?
-        .collect();
-    let saves: Vec<DirEntry> = saves?;
+    if let Some(command) = args.command {
+        return cli::run(command);
+    }
+
+    let saves = saves::list_saves().context(format!("Accessing save directory {SAVES_DIR:?}"))?;
+
+    let overlay = args
+        .overlay
+        .map(OverlayWriter::load)
+        .transpose()
+        .context("Setting up the overlay directory")?;
 
     match args.mode {
         Mode::Tui => {
@@ -56,7 +108,45 @@ fn main() -> anyhow::Result<()> {
 
         Mode::Repl => {
             let mut repl = Repl::new(saves)?;
+            if let Some(overlay) = overlay {
+                repl = repl.with_overlay(overlay)?;
+            }
+            if let Some(policy) = args.autosave {
+                repl = repl.with_autosave(policy);
+            }
             Ok(repl.run())
         }
+
+        Mode::Serve => {
+            let course_list = match args.save {
+                Some(save_name) => {
+                    CourseList::restore_save(save_name).context("Loading the saved course list")?
+                }
+                None => new_list(game::BUILTIN_ID)?,
+            };
+            if let Some(error) = course_list.get_game_error() {
+                println!("{error}. Its courses have placeholder names, and the list is read only.");
+            }
+            if let Some(merge) = course_list.get_game_merge() {
+                println!("{merge}.");
+            }
+            let mut server = Server::new(course_list);
+            if let Some(overlay) = overlay {
+                server = server.with_overlay(overlay)?;
+            }
+            if let Some(ws_port) = args.ws_port {
+                server = server.with_broadcaster(ws_port)?;
+            }
+            if let Some(policy) = args.autosave {
+                server = server.with_autosave(policy);
+            }
+            server.run(args.port)
+        }
     }
 }
+
+// Create a list of every course in a game, to be saved as '<game>.json'
+fn new_list(game: &str) -> anyhow::Result<CourseList> {
+    let definition = GameDefinition::load(game).context(format!("Loading game '{game}'"))?;
+    Ok(CourseList::from_game(format!("{game}.json"), &definition))
+}
@@ -0,0 +1,259 @@
+//! Module defining the `OverlayWriter` struct, which mirrors the state of a `CourseList` into
+//! small text and JSON files, e.g. for use as stream overlay text sources. As an `Observer`, it
+//! rewrites the files on every change.
+//!
+//! The following files are written, each as both `.txt` and `.json`:
+//! - `current_course`: the most recently rolled course.
+//! - `next_courses`: the courses left in the tiered list in progress.
+//! - `remaining`: the number of active courses, and the number of those in the pool.
+//! - `last_rolled`: the last few rolled courses, most recent first.
+//! - `prix_progress`: how far along the tiered list in progress is.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::courses::course::{Course, CourseId};
+use crate::courses::course_list::CourseList;
+use crate::courses::observer::{Change, Observer};
+use crate::write_atomic;
+
+/// Name of the file, inside the overlay directory, holding the `OverlayConfig`.
+pub const CONFIG_FILE: &str = "overlay.json";
+
+/// Templates for the text files. Every `{placeholder}` is replaced by its value. Course
+/// templates accept `{name}`, `{origin}`, `{coord}`, `{rank}` and `{id}`. `{index}`, from before
+/// courses had ids, is replaced by the id as well.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Templates {
+    /// Course template for `current_course.txt`.
+    pub current_course: String,
+    /// Course template for each line of `next_courses.txt`. Also accepts `{label}`, the label of
+    /// the tier the course was drawn from.
+    pub next_course: String,
+    /// Course template for each line of `last_rolled.txt`.
+    pub last_rolled: String,
+    /// Template for `remaining.txt`. Accepts `{remaining}` and `{pool}`.
+    pub remaining: String,
+    /// Template for `prix_progress.txt`. Accepts `{number}`, `{raced}` and `{total}`.
+    pub prix_progress: String,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        Templates {
+            current_course: "{name} ({origin})".to_owned(),
+            next_course: "{label}: {name}".to_owned(),
+            last_rolled: "{name}".to_owned(),
+            remaining: "{remaining} courses remaining".to_owned(),
+            prix_progress: "Race {raced}/{total}".to_owned(),
+        }
+    }
+}
+
+/// Settings for an `OverlayWriter`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct OverlayConfig {
+    /// Number of courses to list in `last_rolled`.
+    pub last_n: usize,
+    /// Templates for the text files.
+    pub templates: Templates,
+}
+
+impl Default for OverlayConfig {
+    fn default() -> Self {
+        OverlayConfig {
+            last_n: 5,
+            templates: Templates::default(),
+        }
+    }
+}
+
+/// Writes overlay files for a `CourseList` into a directory.
+#[derive(Debug, Clone)]
+pub struct OverlayWriter {
+    dir: PathBuf,
+    config: OverlayConfig,
+}
+
+impl OverlayWriter {
+    /// Create a writer for the given directory and settings.
+    pub fn new(dir: impl Into<PathBuf>, config: OverlayConfig) -> Self {
+        OverlayWriter {
+            dir: dir.into(),
+            config,
+        }
+    }
+
+    /// Create a writer for the given directory, creating it if needed. Settings are read from
+    /// `CONFIG_FILE` inside the directory. If there is no such file, one is created with the
+    /// default settings, so they can be customized.
+    ///
+    /// # Errors
+    /// - If the directory cannot be created.
+    /// - If the config file exists but cannot be read or deserialized.
+    /// - If the config file does not exist and cannot be written.
+    pub fn load(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let config_path = dir.join(CONFIG_FILE);
+        let config = if config_path.try_exists()? {
+            serde_json::from_str(&fs::read_to_string(&config_path)?)?
+        } else {
+            let config = OverlayConfig::default();
+            write_atomic(
+                &config_path,
+                serde_json::to_string_pretty(&config)?.as_bytes(),
+            )?;
+            config
+        };
+
+        Ok(Self::new(dir, config))
+    }
+
+    /// Get the directory the files are written to.
+    #[inline]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Write every overlay file for the given list.
+    ///
+    /// # Errors
+    /// - If any file cannot be written.
+    pub fn write(&self, list: &CourseList) -> io::Result<()> {
+        let templates = &self.config.templates;
+        let course_json = |id| json!({ "id": id, "course": list[id] });
+
+        // Courses added back since they were rolled are no longer in play, so they are skipped,
+        // as are repeats of courses rolled more than once
+        let active: BTreeSet<CourseId> = list.get_current().collect();
+        let mut seen = BTreeSet::new();
+        let rolled: Vec<CourseId> = list
+            .recent_removed()
+            .filter(|&id| !active.contains(&id) && seen.insert(id))
+            .take(self.config.last_n.max(1))
+            .collect();
+
+        let current = rolled.first().copied();
+        self.write_pair(
+            "current_course",
+            &current
+                .map(|id| render_course(&templates.current_course, &list[id]))
+                .unwrap_or_default(),
+            &current.map_or(Value::Null, course_json),
+        )?;
+
+        let next = list
+            .get_prix()
+            .map(|p| p.remaining.as_slice())
+            .unwrap_or_default();
+        let next_text: Vec<String> = next
+            .iter()
+            .map(|e| {
+                let line = render_course(&templates.next_course, &list[e.course]);
+                line.replace("{label}", &e.label)
+            })
+            .collect();
+        let next_json: Vec<Value> = next
+            .iter()
+            .map(|e| json!({ "label": e.label, "course": course_json(e.course) }))
+            .collect();
+        self.write_pair("next_courses", &next_text.join("\n"), &json!(next_json))?;
+
+        let remaining = list.get_current().count();
+        let pool = list.get_pool().count();
+        self.write_pair(
+            "remaining",
+            &render(
+                &templates.remaining,
+                &[
+                    ("remaining", remaining.to_string()),
+                    ("pool", pool.to_string()),
+                ],
+            ),
+            &json!({ "remaining": remaining, "pool": pool }),
+        )?;
+
+        let rolled = &rolled[..rolled.len().min(self.config.last_n)];
+        let rolled_text: Vec<String> = rolled
+            .iter()
+            .map(|&id| render_course(&templates.last_rolled, &list[id]))
+            .collect();
+        let rolled_json: Vec<Value> = rolled.iter().map(|&id| course_json(id)).collect();
+        self.write_pair("last_rolled", &rolled_text.join("\n"), &json!(rolled_json))?;
+
+        let progress = list.get_prix().map(|p| {
+            let raced = p.raced.len();
+            (p.number, raced, raced + p.remaining.len())
+        });
+        self.write_pair(
+            "prix_progress",
+            &progress
+                .map(|(number, raced, total)| {
+                    render(
+                        &templates.prix_progress,
+                        &[
+                            ("number", number.to_string()),
+                            ("raced", raced.to_string()),
+                            ("total", total.to_string()),
+                        ],
+                    )
+                })
+                .unwrap_or_default(),
+            &progress.map_or(Value::Null, |(number, raced, total)| {
+                json!({ "number": number, "raced": raced, "total": total })
+            }),
+        )?;
+
+        Ok(())
+    }
+
+    fn write_pair(&self, name: &str, text: &str, json: &Value) -> io::Result<()> {
+        write_atomic(&self.dir.join(format!("{name}.txt")), text.as_bytes())?;
+        write_atomic(
+            &self.dir.join(format!("{name}.json")),
+            serde_json::to_string_pretty(json)?.as_bytes(),
+        )
+    }
+}
+
+impl Observer for OverlayWriter {
+    fn on_change(&mut self, change: &Change) -> io::Result<()> {
+        self.write(change.after).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("writing overlay files to {}: {e}", self.dir.display()),
+            )
+        })
+    }
+}
+
+fn render(template: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(template.to_owned(), |acc, (key, value)| {
+            acc.replace(&format!("{{{key}}}"), value)
+        })
+}
+
+fn render_course(template: &str, course: &Course) -> String {
+    render(
+        template,
+        &[
+            ("name", course.name.clone()),
+            ("origin", course.origin.to_string()),
+            ("coord", course.coord.to_string()),
+            ("rank", course.rank.to_string()),
+            ("id", course.id.to_string()),
+            ("index", course.id.to_string()),
+        ],
+    )
+}
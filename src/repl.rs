@@ -1,20 +1,85 @@
-use std::fs::DirEntry;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use anyhow::{self, Context, bail};
 use my_lib::continue_on_err;
 use my_lib::io::input::{get_input, update_input};
-use rand::Rng;
 
+use mk8d_random::autosave::{Autosave, AutosavePolicy};
+use mk8d_random::courses::aggregate::{AggregationMethod, Contested};
+use mk8d_random::courses::course::{Coord, Course, CourseId, Origin};
 use mk8d_random::courses::course_list::CourseList;
-
-use crate::MK8D_DEFAULT_SAVE_JSON;
+use mk8d_random::courses::edit::CourseEdit;
+use mk8d_random::courses::event::Event;
+use mk8d_random::courses::game;
+use mk8d_random::courses::observer::Change;
+use mk8d_random::courses::player::PoolMode;
+use mk8d_random::courses::results::{PointTable, Standing};
+use mk8d_random::courses::search::{self, SearchMatch};
+use mk8d_random::courses::tiers::TierStrategy;
+use mk8d_random::courses::tournament::{Format, Participant};
+use mk8d_random::overlay::OverlayWriter;
+use mk8d_random::saves::{self, SaveInfo};
+
+use crate::{cli, new_list};
+
+const CONTESTED_SHOWN: usize = 5;
 
 pub struct Repl {
     course_list: CourseList,
+    lines: Option<LineReader>,
+}
+
+// Reads lines from stdin on a background thread, so the REPL can catch up on held back work, e.g.
+// autosaving, while waiting for a command. A line is only read when requested, so prompts that
+// read stdin directly in between are not affected
+struct LineReader {
+    requests: Sender<()>,
+    lines: Receiver<io::Result<String>>,
+}
+
+impl LineReader {
+    fn spawn() -> Self {
+        let (requests, requested) = mpsc::channel::<()>();
+        let (send_line, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for () in requested {
+                let mut line = String::new();
+                let res = io::stdin().read_line(&mut line).map(|_| line);
+                if send_line.send(res).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { requests, lines }
+    }
+
+    fn request(&self) -> io::Result<()> {
+        self.requests.send(()).map_err(|_| Self::stopped())
+    }
+
+    // Wait for the requested line, giving up after `timeout`
+    fn recv(&self, timeout: Duration) -> Option<io::Result<String>> {
+        match self.lines.recv_timeout(timeout) {
+            Ok(line) => Some(line),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Err(Self::stopped())),
+        }
+    }
+
+    fn stopped() -> io::Error {
+        io::Error::other("stopped reading input")
+    }
 }
 
 impl Repl {
-    pub fn new(saves: Vec<DirEntry>) -> anyhow::Result<Self> {
+    pub fn new(saves: Vec<SaveInfo>) -> anyhow::Result<Self> {
         let mut input = String::new();
 
         if saves.is_empty() {
@@ -33,10 +98,11 @@ impl Repl {
     }
 
     fn pick_default() -> anyhow::Result<Self> {
-        println!(
-            "Default options:\n\
-            1 - mk8d"
-        );
+        let games = game::list_games().context("Reading games")?;
+        println!("Default options:");
+        for (i, id) in games.iter().enumerate() {
+            println!("{} - {id}", i + 1);
+        }
         let input = get_input(":> ").context("Reading input")?;
 
         let selection: usize = input
@@ -44,56 +110,90 @@ impl Repl {
             .parse()
             .context(format!("Parsing input '{input}' into number"))?;
 
-        let course_list = match selection {
-            1 => serde_json::from_str(MK8D_DEFAULT_SAVE_JSON).context("Resolving default save")?,
-            _ => bail!("Out of bounds selection"),
+        let Some(id) = games.get(selection.wrapping_sub(1)) else {
+            bail!("Out of bounds selection");
         };
 
-        Ok(Self { course_list })
+        Ok(Self::from_list(new_list(id)?))
     }
 
-    fn load_save(saves: Vec<DirEntry>) -> anyhow::Result<Self> {
+    fn load_save(saves: Vec<SaveInfo>) -> anyhow::Result<Self> {
         println!("Enter the number of the save you want to use:");
-        for (i, dir_entry) in saves.iter().enumerate() {
-            println!("{}: {}", i + 1, dir_entry.file_name().display());
+        let selection = pick_save(&saves)?;
+
+        let course_list = match CourseList::restore_save(&selection.name) {
+            Err(e) if e.kind() == io::ErrorKind::ResourceBusy => {
+                println!("{e}. Open it read only? Changes cannot be saved. (Y/N): ");
+                let input = get_input(":> ").context("Reading input")?;
+                if input.trim().to_lowercase() != "y" {
+                    bail!("Save in use");
+                }
+                CourseList::restore_save_read_only(&selection.name)
+            }
+            res => res,
         }
-        let input = get_input(":> ")?;
-
-        let mut index: usize = input
-            .trim()
-            .parse()
-            .context(format!("Parsing input '{input}' into number"))?;
+        .context("Loading the saved course list")?;
+        Ok(Self::from_list(course_list))
+    }
 
-        index = index.wrapping_sub(1);
+    fn from_list(mut course_list: CourseList) -> Self {
+        if let Some(error) = course_list.get_game_error() {
+            println!("{error}. Its courses have placeholder names, and the list is read only.");
+        }
+        if let Some(merge) = course_list.get_game_merge() {
+            println!("{merge}.");
+            for (id, old) in &merge.renamed {
+                println!("Renamed {old} to {}.", course_list[*id].name);
+            }
+        }
+        course_list.subscribe(report_undo);
+        Self {
+            course_list,
+            lines: None,
+        }
+    }
 
-        let selection = saves
-            .get(index)
-            .ok_or(anyhow::anyhow!("Out of bounds selection"))?;
+    pub fn with_overlay(mut self, overlay: OverlayWriter) -> anyhow::Result<Self> {
+        overlay
+            .write(&self.course_list)
+            .context("Writing overlay files")?;
+        self.course_list.subscribe(overlay);
+        Ok(self)
+    }
 
-        Ok(Self {
-            course_list: CourseList::restore_save(selection.path())
-                .context("Loading the saved course list")?,
-        })
+    pub fn with_autosave(mut self, policy: AutosavePolicy) -> Self {
+        self.course_list.subscribe(Autosave::new(policy));
+        println!("Autosaving {policy}.");
+        self
     }
 
     pub fn run(&mut self) {
         let mut input = String::new();
         println!("Running. Enter 'help' for help information.");
         loop {
-            continue_on_err!(update_input(&mut input, ":> "), "Error reading input");
+            continue_on_err!(self.read_command(&mut input), "Error reading input");
 
             match input.trim().to_lowercase().as_ref() {
                 "" => self.generate(),
 
                 "q" | "quit" => {
-                    continue_on_err!(self.quit());
-                    println!("Quitting...");
-                    break;
+                    if continue_on_err!(self.quit(), "Error") {
+                        println!("Quitting...");
+                        break;
+                    }
                 }
 
                 "help" => Self::help(),
 
-                "save" => continue_on_err!(self.save(), "Error"),
+                "save" => {
+                    continue_on_err!(self.save(), "Error");
+                }
+
+                "saveas" | "save as" => continue_on_err!(self.save_as(), "Error"),
+
+                "fork" => continue_on_err!(self.fork(), "Error"),
+
+                "saves" => continue_on_err!(self.saves(), "Error"),
 
                 "remaining" | "re" | "ls" => self.remaining(),
 
@@ -101,6 +201,10 @@ impl Repl {
 
                 "history" => self.history(),
 
+                "log" => self.log(),
+
+                "export" => continue_on_err!(self.export(), "Error"),
+
                 "reset" => continue_on_err!(self.reset(), "Error"),
 
                 "back" => self.back(),
@@ -111,71 +215,323 @@ impl Repl {
 
                 "remove" | "rm" | "pop" => continue_on_err!(self.remove(), "Error"),
 
+                "create" => continue_on_err!(self.create_course(), "Error"),
+
+                "edit" => continue_on_err!(self.edit_course(), "Error"),
+
+                "delete" => continue_on_err!(self.delete_course(), "Error"),
+
                 "tier" => continue_on_err!(self.tier(), "Error"),
 
+                "veto" => continue_on_err!(self.veto(), "Error"),
+
+                "tournament" => continue_on_err!(self.tournament(), "Error"),
+
+                "rank" => continue_on_err!(self.rank(), "Error"),
+
+                "aggregate" => continue_on_err!(self.aggregate(), "Error"),
+
+                "players" => self.players(),
+
+                "newplayer" => continue_on_err!(self.new_player(), "Error"),
+
+                "present" => continue_on_err!(self.present(), "Error"),
+
+                "ban" => continue_on_err!(self.ban(), "Error"),
+
+                "unban" => continue_on_err!(self.unban(), "Error"),
+
+                "pool" => continue_on_err!(self.pool(), "Error"),
+
+                "result" => continue_on_err!(self.result(), "Error"),
+
+                "standings" => self.standings(),
+
+                "leaders" => self.leaders(),
+
+                "points" => continue_on_err!(self.points(), "Error"),
+
+                "results" => self.results(),
+
+                "editresult" => continue_on_err!(self.edit_result(), "Error"),
+
+                "delresult" => continue_on_err!(self.delete_result(), "Error"),
+
+                "ratings" => self.ratings(),
+
+                "courseratings" => continue_on_err!(self.course_ratings(), "Error"),
+
                 _ => eprintln!("Unrecognized command."),
             }
         }
     }
 
     fn generate(&mut self) {
-        let Some(course_i) = self.course_list.get_random() else {
+        let picker = self.course_list.get_picker().map(|p| p.name.clone());
+
+        let Some(course) = self.course_list.roll() else {
+            if self.course_list.get_current().next().is_some() {
+                println!("None of the remaining courses are acceptable to the players present.");
+                return;
+            }
+
             println!("The course list is empty. Resetting.");
             self.course_list.reset();
             return;
         };
 
-        println!("{}", self.course_list.courses[course_i]);
-        self.course_list.remove(course_i);
+        match picker {
+            Some(name) => println!("{name}'s pick: {}", self.course_list[course]),
+            None => println!("{}", self.course_list[course]),
+        }
     }
 
-    fn quit(&mut self) -> anyhow::Result<()> {
-        println!("Save changes before quitting? (Y/N): ");
-        let input = get_input(":> ").context("Reading input")?;
-        match input.trim().to_lowercase().as_ref() {
-            "y" => {
-                self.course_list.dump_list().context("Saving list")?;
-                println!("Saved successfully.");
-            }
+    // Read a command into `input`, catching up on held back work, e.g. autosaving, while waiting
+    fn read_command(&mut self, input: &mut String) -> io::Result<()> {
+        self.report_observer_errors();
+        let prompt = self.prompt();
+        if self.course_list.next_flush().is_none() {
+            return update_input(input, prompt);
+        }
 
-            "n" => {}
+        print!("{prompt}");
+        io::stdout().flush()?;
+        let lines = self.lines.get_or_insert_with(LineReader::spawn);
+        lines.request()?;
+        loop {
+            let timeout = self.course_list.next_flush().map_or(Duration::MAX, |at| {
+                at.saturating_duration_since(Instant::now())
+            });
+            if let Some(line) = lines.recv(timeout) {
+                *input = line?;
+                return Ok(());
+            }
 
-            _ => {
-                bail!("Must select Y or N");
+            self.course_list.flush_observers(false);
+            for e in self.course_list.take_observer_errors() {
+                eprintln!("\nError: {e}");
             }
         }
+    }
 
-        Ok(())
+    fn report_observer_errors(&mut self) {
+        for e in self.course_list.take_observer_errors() {
+            eprintln!("Error: {e}");
+        }
+    }
+
+    // Marks the prompt when there are unsaved changes
+    fn prompt(&self) -> &'static str {
+        if self.course_list.is_dirty() {
+            "*:> "
+        } else {
+            ":> "
+        }
+    }
+
+    // Returns whether to go ahead and quit
+    fn quit(&mut self) -> anyhow::Result<bool> {
+        self.course_list.flush_observers(true);
+        self.report_observer_errors();
+        if !self.course_list.is_dirty() {
+            return Ok(true);
+        }
+
+        println!("There are unsaved changes. Save, discard them, or cancel? (S/D/C): ");
+        loop {
+            let input = get_input(":> ").context("Reading input")?;
+            match input.trim().to_lowercase().as_ref() {
+                "s" => return self.save(),
+
+                "d" => return Ok(true),
+
+                "c" => return Ok(false),
+
+                _ => {
+                    println!("Must select S, D or C.");
+                }
+            }
+        }
     }
 
-    fn save(&self) -> anyhow::Result<()> {
+    // Returns whether the list was saved
+    fn save(&self) -> anyhow::Result<bool> {
+        if self
+            .course_list
+            .modified_externally()
+            .context("Checking the save file")?
+        {
+            let input = get_input(
+                "The save file was modified since it was loaded. Overwrite it? (capital 'Y' to \
+                confirm): ",
+            )
+            .context("Reading input")?;
+            if input.trim() != "Y" {
+                println!("Cancelled save.");
+                return Ok(false);
+            }
+        }
+
         self.course_list.dump_list().context("Saving list")?;
         println!("Saved successfully.");
+        Ok(true)
+    }
+
+    fn save_as(&mut self) -> anyhow::Result<()> {
+        println!("Enter the name to save the list as (e.g. 'save.json'):");
+        let name = get_input(":> ").context("Reading input")?;
+
+        let saved = match self.course_list.save_as(&name, false) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if !confirm_overwrite(&e)? {
+                    println!("Cancelled save.");
+                    return Ok(());
+                }
+                self.course_list.save_as(&name, true)
+            }
+            res => res,
+        }
+        .context("Saving list")?;
+
+        println!(
+            "Saved as {}. This session now uses that save.",
+            saved.display()
+        );
+        Ok(())
+    }
+
+    fn fork(&self) -> anyhow::Result<()> {
+        println!("Enter the name of the new save (e.g. 'save.json'):");
+        let name = get_input(":> ").context("Reading input")?;
+
+        let forked = match self.course_list.fork(&name, false) {
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                if !confirm_overwrite(&e)? {
+                    println!("Cancelled fork.");
+                    return Ok(());
+                }
+                self.course_list.fork(&name, true)
+            }
+            res => res,
+        }
+        .context("Forking list")?;
+
+        println!(
+            "Forked the list and its history to {}. This session still uses {}.",
+            forked.display(),
+            self.course_list.save_name.display()
+        );
+        Ok(())
+    }
+
+    fn saves(&mut self) -> anyhow::Result<()> {
+        println!(
+            "Entered save manager. Commands:\n\
+            ls:     List saves.\n\
+            rename: Rename a save.\n\
+            copy:   Copy a save.\n\
+            delete: Move a save to the trash.\n\
+            new:    Create a save with the courses of this list, all active, and no history.\n\
+            back:   Return to the main list."
+        );
+        Self::list_saves()?;
+
+        let mut input = String::new();
+        loop {
+            continue_on_err!(self.read_command(&mut input), "Error reading input");
+
+            match input.trim().to_lowercase().as_ref() {
+                "ls" => continue_on_err!(Self::list_saves(), "Error"),
+
+                "rename" => {
+                    let (from, to) = continue_on_err!(Self::pick_save_and_name(), "Error");
+                    let to = continue_on_err!(
+                        saves::rename_save(&from, &to).context("Renaming save"),
+                        "Error"
+                    );
+                    println!("Renamed {} to {}.", from.display(), to.display());
+                }
+
+                "copy" => {
+                    let (from, to) = continue_on_err!(Self::pick_save_and_name(), "Error");
+                    let to = continue_on_err!(
+                        saves::copy_save(&from, &to).context("Copying save"),
+                        "Error"
+                    );
+                    println!("Copied {} to {}.", from.display(), to.display());
+                }
+
+                "delete" => {
+                    let saves = continue_on_err!(saves::list_saves(), "Error reading saves");
+                    println!("Enter the number of the save to delete:");
+                    let name = continue_on_err!(pick_save(&saves), "Error").name.clone();
+                    continue_on_err!(saves::delete_save(&name).context("Deleting save"), "Error");
+                    println!("Moved {} to the trash.", name.display());
+                }
+
+                "new" => {
+                    let name = continue_on_err!(
+                        get_input("Name of the new save (e.g. 'save.json'): "),
+                        "Error reading input"
+                    );
+                    let name = continue_on_err!(
+                        saves::create_save(&name, &self.course_list).context("Creating save"),
+                        "Error"
+                    );
+                    println!("Created {}.", name.display());
+                }
+
+                "back" => {
+                    println!("Returning to main list...");
+                    return Ok(());
+                }
+
+                _ => println!("Unrecognized command."),
+            }
+        }
+    }
+
+    fn list_saves() -> anyhow::Result<()> {
+        let saves = saves::list_saves().context("Reading saves")?;
+        if saves.is_empty() {
+            println!("There are no saves.");
+        }
+        for save in saves {
+            println!("{save}");
+        }
         Ok(())
     }
 
+    fn pick_save_and_name() -> anyhow::Result<(PathBuf, String)> {
+        let saves = saves::list_saves().context("Reading saves")?;
+        println!("Enter the number of the save:");
+        let from = pick_save(&saves)?.name.clone();
+        let to = get_input("New name (e.g. 'save.json'): ").context("Reading input")?;
+        Ok((from, to))
+    }
+
     fn remaining(&self) {
-        let current: Vec<usize> = self.course_list.get_current().collect();
+        let current: Vec<CourseId> = self.course_list.get_current().collect();
         if current.is_empty() {
             println!("The course list is empty.");
             return;
         }
 
-        for i in &current {
-            println!("{}", self.course_list.courses[*i]);
+        for id in &current {
+            println!("{}", self.course_list[*id]);
         }
         println!("There are {} courses in the list.", current.len());
     }
 
     fn used(&self) {
-        let removed: Vec<usize> = self.course_list.get_removed().collect();
+        let removed: Vec<CourseId> = self.course_list.get_removed().collect();
         if removed.is_empty() {
             println!("No courses have been used.");
             return;
         }
 
-        for i in &removed {
-            println!("{}", self.course_list.courses[*i]);
+        for id in &removed {
+            println!("{}", self.course_list[*id]);
         }
         println!("{} courses have been used.", removed.len());
     }
@@ -183,7 +539,31 @@ impl Repl {
     fn history(&self) {
         let history = self.course_list.get_history();
         let courses = &self.course_list.courses;
-        println!("{}", history.to_string(courses));
+        let players = self.course_list.get_players();
+        println!("{}", history.to_string(courses, players));
+    }
+
+    fn log(&self) {
+        let history = self.course_list.get_history();
+        let courses = &self.course_list.courses;
+        let players = self.course_list.get_players();
+        let log = history.log(courses, players);
+        if log.is_empty() {
+            println!("No history.");
+            return;
+        }
+
+        for (i, action) in log.iter().enumerate() {
+            println!("{}: {action}", i + 1);
+        }
+    }
+
+    fn export(&self) -> anyhow::Result<()> {
+        println!("Enter the file to write the report to (e.g. 'recap.md' or 'recap.html'):");
+        let file = PathBuf::from(get_input(":> ").context("Reading input")?);
+        cli::write_report(&self.course_list, &file)?;
+        println!("Wrote a report to {}.", file.display());
+        Ok(())
     }
 
     fn reset(&mut self) -> anyhow::Result<()> {
@@ -218,25 +598,169 @@ impl Repl {
 
     fn add(&mut self) -> anyhow::Result<()> {
         let input = get_input("Search courses: ").context("Reading input")?;
-        let results: Vec<usize> = self.course_list.search_removed(&input).collect();
+        let results = self.course_list.search_removed(&input);
 
-        let selection = self.search_sub_list(results)?;
+        let selection = self.pick_match(&input, results)?;
         self.course_list.add(selection);
         Ok(())
     }
 
     fn remove(&mut self) -> anyhow::Result<()> {
         let input = get_input("Search courses: ").context("Reading input")?;
-        let results: Vec<usize> = self.course_list.search_current(&input).collect();
+        let results = self.course_list.search_current(&input);
 
-        let selection = self.search_sub_list(results)?;
+        let selection = self.pick_match(&input, results)?;
         self.course_list.remove(selection);
         Ok(())
     }
 
-    fn search_sub_list(&self, sub_list: Vec<usize>) -> anyhow::Result<usize> {
-        for (i, course_i) in sub_list.iter().enumerate() {
-            println!("{}: {}", i + 1, self.course_list.courses[*course_i]);
+    fn create_course(&mut self) -> anyhow::Result<()> {
+        self.warn_unlink();
+        let name = get_input("Enter the course's name: ").context("Reading input")?;
+        let aliases = get_input("Enter its aliases (e.g. RR, DKJ), separated by commas: ")
+            .context("Reading input")?;
+        let origin: Origin = get_input("Enter its origin (e.g. Wii, 3DS, Tour): ")
+            .context("Reading input")?
+            .parse()
+            .map_err(anyhow::Error::msg)?;
+        let coord: Coord = get_input("Enter its coordinate (row-col-pos, e.g. 3-1-4): ")
+            .context("Reading input")?
+            .parse()
+            .map_err(anyhow::Error::msg)?;
+
+        let lowest = self.course_list.courses.len() + 1;
+        let input = get_input(&format!("Enter its rank (blank for {lowest}): "))
+            .context("Reading input")?;
+        let rank = if input.trim().is_empty() {
+            lowest
+        } else {
+            input
+                .trim()
+                .parse()
+                .context(format!("Parsing input '{input}' into number"))?
+        };
+
+        let input = get_input("Enter its tags, separated by commas: ").context("Reading input")?;
+        let mut course = Course::new(CourseId::default(), rank, coord, origin, &name);
+        course.aliases = parse_list(&aliases);
+        course.tags = parse_list(&input);
+
+        let id = self.course_list.create_course(course)?;
+        println!("Created {}.", self.course_list[id]);
+        Ok(())
+    }
+
+    fn edit_course(&mut self) -> anyhow::Result<()> {
+        let input = get_input("Search courses: ").context("Reading input")?;
+        let results: Vec<CourseId> = self
+            .course_list
+            .search_all(&input)
+            .into_iter()
+            .map(|m| m.course)
+            .collect();
+        let id = self.search_sub_list(results)?;
+        let course = self.course_list[id].clone();
+        self.warn_unlink();
+        println!("Leave a field blank to keep it.");
+
+        let mut edit = CourseEdit::default();
+        let input = get_input(&format!("Name ({}): ", course.name)).context("Reading input")?;
+        if !input.trim().is_empty() {
+            edit.name = Some(input);
+        }
+        edit.aliases = Self::edit_list("Aliases", &course.aliases)?;
+        let input = get_input(&format!("Origin ({}): ", course.origin)).context("Reading input")?;
+        if !input.trim().is_empty() {
+            edit.origin = Some(input.parse().map_err(anyhow::Error::msg)?);
+        }
+        let input =
+            get_input(&format!("Coordinate ({}): ", course.coord)).context("Reading input")?;
+        if !input.trim().is_empty() {
+            edit.coord = Some(input.parse().map_err(anyhow::Error::msg)?);
+        }
+        let input = get_input(&format!("Rank ({}): ", course.rank)).context("Reading input")?;
+        if !input.trim().is_empty() {
+            edit.rank = Some(
+                input
+                    .trim()
+                    .parse()
+                    .context(format!("Parsing input '{input}' into number"))?,
+            );
+        }
+        edit.tags = Self::edit_list("Tags", &course.tags)?;
+
+        self.course_list.edit_course(id, edit)?;
+        println!("Updated {}.", self.course_list[id]);
+        Ok(())
+    }
+
+    // Prompt for a new comma separated list to replace `current`. Blank keeps it, '-' clears it
+    fn edit_list(field: &str, current: &[String]) -> anyhow::Result<Option<Vec<String>>> {
+        let input = get_input(&format!(
+            "{field}, separated by commas, or '-' for none ({}): ",
+            current.join(", ")
+        ))
+        .context("Reading input")?;
+
+        Ok(match input.trim() {
+            "" => None,
+            "-" => Some(Vec::new()),
+            _ => Some(parse_list(&input)),
+        })
+    }
+
+    fn delete_course(&mut self) -> anyhow::Result<()> {
+        let input = get_input("Search courses: ").context("Reading input")?;
+        let results: Vec<CourseId> = self
+            .course_list
+            .search_all(&input)
+            .into_iter()
+            .map(|m| m.course)
+            .collect();
+        let id = self.search_sub_list(results)?;
+        self.warn_unlink();
+
+        let input = get_input(&format!(
+            "Delete {}? (capital 'Y' to confirm): ",
+            self.course_list[id].name
+        ))
+        .context("Reading input")?;
+        if input.trim() != "Y" {
+            println!("Cancelled deletion.");
+            return Ok(());
+        }
+
+        let course = self.course_list.delete_course(id)?;
+        println!("Deleted {}.", course.name);
+        Ok(())
+    }
+
+    // Changing the courses of a list unlinks it from its game, so say so before it happens
+    fn warn_unlink(&self) {
+        if let Some(game) = self.course_list.get_game() {
+            println!(
+                "This list follows the game {game}. Changing its courses unlinks it, so it will \
+                no longer pick up updates to the game unless the change is undone."
+            );
+        }
+    }
+
+    // Take the strong match of a search without asking, if it has one, otherwise let the user pick
+    fn pick_match(&self, searched: &str, results: Vec<SearchMatch>) -> anyhow::Result<CourseId> {
+        if results.is_empty() {
+            bail!("No courses found");
+        }
+        if let Some(course) = search::strong_match(&results, searched) {
+            println!("Selected {}.", self.course_list[course]);
+            return Ok(course);
+        }
+
+        self.search_sub_list(results.into_iter().map(|m| m.course).collect())
+    }
+
+    fn search_sub_list(&self, sub_list: Vec<CourseId>) -> anyhow::Result<CourseId> {
+        for (i, course) in sub_list.iter().enumerate() {
+            println!("{}: {}", i + 1, self.course_list[*course]);
         }
 
         let input = get_input("Select a number: ").context("Reading input")?;
@@ -253,57 +777,113 @@ impl Repl {
     }
 
     fn tier(&mut self) -> anyhow::Result<()> {
-        let input = get_input("Enter the size of the prix: ").context("Reading input")?;
-        let size: usize = input
-            .parse()
-            .context(format!("Parsing input '{input}' into number"))?;
+        if self
+            .course_list
+            .get_prix()
+            .is_some_and(|prix| !prix.is_finished())
+        {
+            let input = get_input("Resume the tiered list in progress? (Y/N): ")
+                .context("Reading input")?;
+            match input.trim().to_lowercase().as_ref() {
+                "y" => {
+                    self.run_tiered_list();
+                    return Ok(());
+                }
 
-        let tiered_courses: Vec<usize> = match self.course_list.get_random_by_chunks(size) {
-            Ok(i) => i.collect(),
-            Err(_) => {
-                bail!(
-                    "Could not divide courses into tiers.\n\
-                    This probably means the course list cannot be evenly divided by the given prix size.\n\
-                    Consider adding or removing courses until the list is evenly divisible."
-                );
+                "n" => {}
+
+                _ => bail!("Must select Y or N"),
             }
-        };
+        }
 
-        if self.run_tiered_list(tiered_courses.clone()) {
-            for course in tiered_courses {
-                self.course_list.remove(course);
+        println!(
+            "Tiering strategies:\n\
+            1 - Even chunks by rank (default)\n\
+            2 - Rank percentiles\n\
+            3 - Origin\n\
+            4 - Custom rank boundaries"
+        );
+        let input = get_input("Select a strategy: ").context("Reading input")?;
+
+        let strategy = match input.trim() {
+            "" | "1" => TierStrategy::Even(Self::get_prix_size()?),
+            "2" => TierStrategy::Percentile(Self::get_prix_size()?),
+            "3" => TierStrategy::Origin,
+            "4" => {
+                let input = get_input("Enter the last rank of each tier, separated by spaces: ")
+                    .context("Reading input")?;
+                let bounds: Vec<usize> = input
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .context(format!("Parsing input '{input}' into numbers"))?;
+                TierStrategy::Boundaries(bounds)
             }
+            _ => bail!("Out of bounds selection"),
+        };
+
+        if self.course_list.start_prix(&strategy).is_err() {
+            bail!(
+                "Could not divide courses into tiers.\n\
+                This probably means there are fewer courses than the given prix size, or the \
+                given rank boundaries are not increasing."
+            );
         }
 
+        self.run_tiered_list();
         Ok(())
     }
 
-    fn run_tiered_list(&mut self, mut list: Vec<usize>) -> bool {
-        let mut rng = rand::rng();
+    fn get_prix_size() -> anyhow::Result<usize> {
+        let input = get_input("Enter the size of the prix: ").context("Reading input")?;
+        input
+            .trim()
+            .parse()
+            .context(format!("Parsing input '{input}' into number"))
+    }
+
+    fn run_tiered_list(&mut self) {
         println!(
-            "Entered tiered list. Type 'back' to return without removing the selected courses."
+            "Entered tiered list. Courses are removed as they are raced.\n\
+            Type 'result' to record placements for a raced course.\n\
+            Type 'back' to return and resume later, or 'end' to discard the rest of the list."
         );
 
         let mut input: String = String::new();
-        while !list.is_empty() {
-            continue_on_err!(update_input(&mut input, ":> "), "Error reading input");
+        while self
+            .course_list
+            .get_prix()
+            .is_some_and(|prix| !prix.is_finished())
+        {
+            continue_on_err!(self.read_command(&mut input), "Error reading input");
 
             match input.trim().to_lowercase().as_ref() {
                 "" => {
-                    let sub_index = rng.random_range(0..list.len());
-                    let course_index = list[sub_index];
-                    println!("{}", self.course_list.courses[course_index]);
-                    list.remove(sub_index);
+                    if let Some(entry) = self.course_list.roll_prix() {
+                        let course = &self.course_list[entry.course];
+                        println!("{}: {course}", entry.label);
+                    }
                 }
 
                 "back" => {
                     println!("Returning to main list...");
-                    return false;
+                    return;
+                }
+
+                "end" => {
+                    self.course_list.end_prix();
+                    println!("Tiered list discarded. Returning to main list...");
+                    return;
                 }
 
+                "result" => continue_on_err!(self.result(), "Error"),
+
                 "ls" => {
-                    for i in &list {
-                        println!("{}", self.course_list.courses[*i]);
+                    if let Some(prix) = self.course_list.get_prix() {
+                        for entry in &prix.remaining {
+                            let course = &self.course_list[entry.course];
+                            println!("{}: {course}", entry.label);
+                        }
                     }
                 }
 
@@ -313,41 +893,947 @@ impl Repl {
             }
         }
 
+        self.course_list.end_prix();
         println!("Tiered list exhausted. Returning to main list...");
-        true
     }
 
-    fn help() {
-        println!("---------------------------------------------------");
+    fn veto(&mut self) -> anyhow::Result<()> {
+        if self.course_list.get_veto().is_none() {
+            let input =
+                get_input("Enter the number of candidate courses: ").context("Reading input")?;
+            let num_candidates: usize = input
+                .trim()
+                .parse()
+                .context(format!("Parsing input '{input}' into number"))?;
 
-        println!(
-            "Blank input: Generate and remove a random course.\n\
-            q, quit:      Exit.\n\
-            help:         Show this help text.\n\
-            save:         Save the list."
-        );
+            if self.course_list.start_veto(num_candidates).is_err() {
+                bail!(
+                    "Could not start the veto phase.\n\
+                    There must be players present, more candidates than players, and at least as \
+                    many courses in the pool as candidates."
+                );
+            }
+        }
 
         println!(
-            "Information:\n\
-            remaining, re, ls: List remaining courses.\n\
-            used:              List used courses.\n\
-            history:           Show history.\n"
+            "Entered veto phase. Enter the number of a course to strike it.\n\
+            Type 'back' to return and resume later, or 'end' to discard the veto phase."
         );
 
-        println!(
-            "List editing:\n\
-            reset:           Reset the course list and delete all history.\n\
-            back:            Roll back in history.\n\
-            forward:         Roll forward in history.\n\
-            add:             Add a previously removed course.\n\
-            remove, rm, pop: Remove a currently active course.\n"
-        );
+        let mut input = String::new();
+        loop {
+            let Some(veto) = self.course_list.get_veto() else {
+                return Ok(());
+            };
+            let Some(striker) = veto.striker() else {
+                break;
+            };
+            let candidates = veto.candidates.clone();
+
+            for (i, course) in candidates.iter().enumerate() {
+                println!("{}: {}", i + 1, self.course_list[*course]);
+            }
+            let name = &self.course_list.get_players()[striker].name;
+            println!("{name}, strike a course:");
 
-        println!(
-            "Special:\n\
-            tier: Generate a tiered sub-list."
-        );
+            continue_on_err!(self.read_command(&mut input), "Error reading input");
 
-        println!("---------------------------------------------------");
-    }
+            match input.trim().to_lowercase().as_ref() {
+                "back" => {
+                    println!("Returning to main list...");
+                    return Ok(());
+                }
+
+                "end" => {
+                    self.course_list.end_veto();
+                    println!("Veto phase discarded. Returning to main list...");
+                    return Ok(());
+                }
+
+                selection => {
+                    let Some(&course) = selection
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|i| candidates.get(i.wrapping_sub(1)))
+                    else {
+                        println!("Unrecognized command.");
+                        continue;
+                    };
+
+                    if self.course_list.strike(course).is_err() {
+                        bail!("Could not strike that course");
+                    }
+                }
+            }
+        }
+
+        if self.course_list.race_survivors().is_err() {
+            bail!("Could not race the surviving courses");
+        }
+        println!("Veto phase complete. Racing the surviving courses.");
+        self.run_tiered_list();
+        Ok(())
+    }
+
+    fn result(&mut self) -> anyhow::Result<()> {
+        let last = self
+            .course_list
+            .last_removed()
+            .ok_or(anyhow::anyhow!("No course has been rolled yet"))?;
+        let input = get_input(&format!(
+            "Enter the course raced (blank for {}): ",
+            self.course_list[last].name
+        ))
+        .context("Reading input")?;
+        let course = if input.trim().is_empty() {
+            last
+        } else {
+            self.pick_match(&input, self.course_list.search_removed(&input))?
+        };
+        println!("Recording the result for {}", self.course_list[course]);
+
+        let names: Vec<String> = self
+            .course_list
+            .present_players()
+            .map(|p| p.name.clone())
+            .collect();
+        if names.is_empty() {
+            bail!("No players are present. Use 'present' to set who is playing.");
+        }
+
+        let placements = Self::read_placements(names)?;
+        if self.course_list.record_result(course, placements).is_err() {
+            bail!("Placements must be unique, start at 1, and include at least one player");
+        }
+        println!("Result recorded.");
+        Ok(())
+    }
+
+    fn read_placements(names: Vec<String>) -> anyhow::Result<BTreeMap<String, u8>> {
+        let mut placements: BTreeMap<String, u8> = BTreeMap::new();
+        for name in names {
+            let input = get_input(&format!("Placement for {name} (blank to skip): "))
+                .context("Reading input")?;
+            if input.trim().is_empty() {
+                continue;
+            }
+
+            let placement: u8 = input
+                .trim()
+                .parse()
+                .context(format!("Parsing input '{input}' into number"))?;
+            placements.insert(name, placement);
+        }
+        Ok(placements)
+    }
+
+    fn standings(&self) {
+        if self.course_list.get_results().is_empty() {
+            println!("No results have been recorded.");
+            return;
+        }
+
+        if let Some(prix) = self.course_list.get_prix() {
+            println!("Prix {} standings:", prix.number);
+            Self::print_standings(&self.course_list.prix_standings(prix.number));
+        }
+
+        println!("Season standings:");
+        Self::print_standings(&self.course_list.season_standings());
+    }
+
+    fn print_standings(standings: &[Standing]) {
+        for (i, s) in standings.iter().enumerate() {
+            println!(
+                "{:>2}. {:<16} {:>4} pts ({} races, {} wins)",
+                i + 1,
+                s.player,
+                s.points,
+                s.races,
+                s.wins
+            );
+        }
+    }
+
+    fn leaders(&self) {
+        let leaders = self.course_list.course_leaders();
+        if leaders.is_empty() {
+            println!("No results have been recorded.");
+            return;
+        }
+
+        for leader in leaders {
+            println!(
+                "{}: {} (average {:.1} over {} races)",
+                self.course_list[leader.course], leader.player, leader.average, leader.races
+            );
+        }
+    }
+
+    fn results(&self) {
+        let results = self.course_list.get_results();
+        if results.is_empty() {
+            println!("No results have been recorded.");
+            return;
+        }
+
+        for (i, result) in results.iter().enumerate() {
+            let placements: Vec<String> = result
+                .placements
+                .iter()
+                .map(|(name, placement)| format!("{name} {placement}"))
+                .collect();
+            println!(
+                "{}: {} - {}",
+                i + 1,
+                self.course_list[result.course].name,
+                placements.join(", ")
+            );
+        }
+    }
+
+    fn edit_result(&mut self) -> anyhow::Result<()> {
+        self.results();
+        let input =
+            get_input("Enter the number of the result to edit: ").context("Reading input")?;
+        let index: usize = input
+            .trim()
+            .parse()
+            .context(format!("Parsing input '{input}' into number"))?;
+        let result = self
+            .course_list
+            .get_results()
+            .get(index.wrapping_sub(1))
+            .ok_or(anyhow::anyhow!("Out of bounds selection"))?;
+
+        // Players who raced, then anyone present who was left out
+        let mut names: Vec<String> = result.placements.keys().cloned().collect();
+        for player in self.course_list.present_players() {
+            if !names.contains(&player.name) {
+                names.push(player.name.clone());
+            }
+        }
+
+        let placements = Self::read_placements(names)?;
+        if self.course_list.edit_result(index - 1, placements).is_err() {
+            bail!("Placements must be unique, start at 1, and include at least one player");
+        }
+        println!("Result edited.");
+        Ok(())
+    }
+
+    fn delete_result(&mut self) -> anyhow::Result<()> {
+        self.results();
+        let input =
+            get_input("Enter the number of the result to delete: ").context("Reading input")?;
+        let index: usize = input
+            .trim()
+            .parse()
+            .context(format!("Parsing input '{input}' into number"))?;
+
+        if self
+            .course_list
+            .remove_result(index.wrapping_sub(1))
+            .is_err()
+        {
+            bail!("Out of bounds selection");
+        }
+        println!("Result deleted.");
+        Ok(())
+    }
+
+    fn ratings(&self) {
+        let ratings = self.course_list.ratings();
+        let leaderboard = ratings.leaderboard();
+        if leaderboard.is_empty() {
+            println!("No results have been recorded.");
+            return;
+        }
+
+        for (i, (name, rating)) in leaderboard.iter().enumerate() {
+            let history = ratings.history.get(*name).map_or(&[][..], Vec::as_slice);
+            println!(
+                "{:>2}. {name:<16} {rating:>6.0} {}",
+                i + 1,
+                Self::sparkline(history)
+            );
+        }
+    }
+
+    fn course_ratings(&self) -> anyhow::Result<()> {
+        let input = get_input("Search courses: ").context("Reading input")?;
+        let results: Vec<CourseId> = self
+            .course_list
+            .search_all(&input)
+            .into_iter()
+            .map(|m| m.course)
+            .collect();
+        let selection = self.search_sub_list(results)?;
+
+        let ratings = self.course_list.ratings();
+        let Some(course_ratings) = ratings.per_course.get(&selection) else {
+            println!("No results have been recorded on that course.");
+            return Ok(());
+        };
+
+        let mut course_ratings: Vec<(&String, &f64)> = course_ratings.iter().collect();
+        course_ratings.sort_by(|a, b| b.1.total_cmp(a.1));
+        for (i, (name, rating)) in course_ratings.iter().enumerate() {
+            println!("{:>2}. {name:<16} {rating:>6.0}", i + 1);
+        }
+        Ok(())
+    }
+
+    // Draw a series of values as a one-line chart
+    fn sparkline(values: &[f64]) -> String {
+        const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = max - min;
+
+        values
+            .iter()
+            .map(|&v| {
+                if range <= f64::EPSILON {
+                    return BARS[BARS.len() / 2];
+                }
+
+                #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+                let level = ((v - min) / range * 7.0).round() as usize;
+                BARS[level.min(BARS.len() - 1)]
+            })
+            .collect()
+    }
+
+    fn points(&mut self) -> anyhow::Result<()> {
+        let current: Vec<String> = self
+            .course_list
+            .get_point_table()
+            .0
+            .iter()
+            .map(u32::to_string)
+            .collect();
+        println!("Current points per placement: {}", current.join(" "));
+
+        let input = get_input(
+            "Enter the points for each placement, separated by spaces (blank for the default): ",
+        )
+        .context("Reading input")?;
+
+        let table = if input.trim().is_empty() {
+            PointTable::default()
+        } else {
+            PointTable(
+                input
+                    .split_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()
+                    .context(format!("Parsing input '{input}' into numbers"))?,
+            )
+        };
+
+        self.course_list.set_point_table(table);
+        println!("Point table updated.");
+        Ok(())
+    }
+
+    fn tournament(&mut self) -> anyhow::Result<()> {
+        if self.course_list.get_tournament().is_none() {
+            self.new_tournament()?;
+        }
+
+        println!(
+            "Entered tournament. Commands:\n\
+            Blank input, next: Generate the next round.\n\
+            ls:                Show the bracket.\n\
+            result:            Record placements for a course of a match in the current round.\n\
+            win:               Record the winner of a match in the current round.\n\
+            export:            Export the bracket as JSON and text.\n\
+            back:              Return and resume later.\n\
+            end:               Discard the tournament."
+        );
+
+        let mut input = String::new();
+        loop {
+            continue_on_err!(self.read_command(&mut input), "Error reading input");
+
+            match input.trim().to_lowercase().as_ref() {
+                "" | "next" => {
+                    let round = continue_on_err!(self.course_list.next_tournament_round(), "Error")
+                        .to_vec();
+                    for (i, m) in round.iter().enumerate() {
+                        if m.is_bye() {
+                            println!("{}: {} (bye)", i + 1, m.sides[0]);
+                            continue;
+                        }
+
+                        println!("{}: {}", i + 1, m.sides.join(" vs "));
+                        for &c in &m.courses {
+                            println!("    {}", self.course_list[c]);
+                        }
+                    }
+                }
+
+                "ls" => {
+                    if let Some(tournament) = self.course_list.get_tournament() {
+                        print!("{}", tournament.to_text(&self.course_list.courses));
+                    }
+                }
+
+                "result" => continue_on_err!(self.match_result(), "Error"),
+
+                "win" => continue_on_err!(self.match_winner(), "Error"),
+
+                "export" => continue_on_err!(self.export_tournament(), "Error"),
+
+                "back" => {
+                    println!("Returning to main list...");
+                    return Ok(());
+                }
+
+                "end" => {
+                    self.course_list.end_tournament();
+                    println!("Tournament discarded. Returning to main list...");
+                    return Ok(());
+                }
+
+                _ => println!("Unrecognized command."),
+            }
+
+            if let Some(champion) = self.course_list.get_tournament().and_then(|t| t.champion()) {
+                println!("The tournament is finished. Champion: {champion}");
+            }
+        }
+    }
+
+    fn new_tournament(&mut self) -> anyhow::Result<()> {
+        println!(
+            "Tournament formats:\n\
+            1 - Round robin\n\
+            2 - Single elimination\n\
+            3 - Double elimination\n\
+            4 - Swiss"
+        );
+        let input = get_input("Select a format: ").context("Reading input")?;
+
+        let format = match input.trim() {
+            "1" => Format::RoundRobin,
+            "2" => Format::SingleElimination,
+            "3" => Format::DoubleElimination,
+            "4" => {
+                let input = get_input("Enter the number of rounds: ").context("Reading input")?;
+                Format::Swiss(
+                    input
+                        .trim()
+                        .parse()
+                        .context(format!("Parsing input '{input}' into number"))?,
+                )
+            }
+            _ => bail!("Out of bounds selection"),
+        };
+
+        println!(
+            "Enter participants one per line, in seeding order, and a blank line when done.\n\
+            Write a team as 'Team name: member, member, ...'.\n\
+            Leave the first line blank to use the players present."
+        );
+        let mut participants: Vec<Participant> = Vec::new();
+        loop {
+            let input = get_input(":> ").context("Reading input")?;
+            let line = input.trim();
+            if line.is_empty() {
+                break;
+            }
+
+            participants.push(match line.split_once(':') {
+                Some((team, members)) => Participant {
+                    name: team.trim().to_owned(),
+                    members: parse_list(members),
+                },
+                None => Participant {
+                    name: line.to_owned(),
+                    members: vec![line.to_owned()],
+                },
+            });
+        }
+
+        if participants.is_empty() {
+            participants = self
+                .course_list
+                .present_players()
+                .map(|p| Participant {
+                    name: p.name.clone(),
+                    members: vec![p.name.clone()],
+                })
+                .collect();
+        }
+
+        let input =
+            get_input("Enter the number of courses per match: ").context("Reading input")?;
+        let courses_per_match: usize = input
+            .trim()
+            .parse()
+            .context(format!("Parsing input '{input}' into number"))?;
+
+        self.course_list
+            .start_tournament(format, participants, courses_per_match)
+            .context("Creating tournament")?;
+        Ok(())
+    }
+
+    // Ask for a match of the current round, returning its round and match indices
+    fn select_match(&self) -> anyhow::Result<(usize, usize)> {
+        let round_i = self
+            .course_list
+            .get_tournament()
+            .map(|t| t.rounds.len())
+            .filter(|&len| len > 0)
+            .ok_or(anyhow::anyhow!("No round has been generated yet"))?
+            - 1;
+
+        let input = get_input("Enter the match number: ").context("Reading input")?;
+        let match_i: usize = input
+            .trim()
+            .parse()
+            .context(format!("Parsing input '{input}' into number"))?;
+        Ok((round_i, match_i.wrapping_sub(1)))
+    }
+
+    fn match_result(&mut self) -> anyhow::Result<()> {
+        let (round_i, match_i) = self.select_match()?;
+        let tournament = self
+            .course_list
+            .get_tournament()
+            .ok_or(anyhow::anyhow!("No tournament in progress"))?;
+        let m = tournament
+            .rounds
+            .get(round_i)
+            .and_then(|r| r.get(match_i))
+            .filter(|m| !m.is_bye())
+            .ok_or(anyhow::anyhow!("No such match"))?;
+
+        let names: Vec<String> = m
+            .sides
+            .iter()
+            .flat_map(|side| {
+                let participant = tournament.participants.iter().find(|p| &p.name == side);
+                participant.map_or(&[][..], |p| p.members.as_slice())
+            })
+            .cloned()
+            .collect();
+        let course = self.search_sub_list(m.courses.clone())?;
+        println!("Recording the result for {}", self.course_list[course]);
+
+        let placements = Self::read_placements(names)?;
+        self.course_list
+            .record_match_result(round_i, match_i, course, placements)
+            .context("Recording result")?;
+        println!("Result recorded.");
+        Ok(())
+    }
+
+    fn match_winner(&mut self) -> anyhow::Result<()> {
+        let (round_i, match_i) = self.select_match()?;
+
+        let input = get_input("Enter the winner (blank to decide from recorded results): ")
+            .context("Reading input")?;
+        let winner = Some(input.trim()).filter(|w| !w.is_empty());
+
+        let winner = self
+            .course_list
+            .record_match_winner(round_i, match_i, winner)
+            .context("Recording winner")?;
+        println!("{winner} wins the match.");
+        Ok(())
+    }
+
+    fn export_tournament(&self) -> anyhow::Result<()> {
+        let tournament = self
+            .course_list
+            .get_tournament()
+            .ok_or(anyhow::anyhow!("No tournament in progress"))?;
+
+        let input =
+            get_input("Enter the path to export to, without an extension (blank for 'bracket'): ")
+                .context("Reading input")?;
+        let base = PathBuf::from(
+            Some(input.trim())
+                .filter(|p| !p.is_empty())
+                .unwrap_or("bracket"),
+        );
+
+        let json = serde_json::to_string_pretty(tournament).context("Serializing tournament")?;
+        let json_path = base.with_extension("json");
+        fs::write(&json_path, json).context(format!("Writing {}", json_path.display()))?;
+
+        let text_path = base.with_extension("txt");
+        fs::write(&text_path, tournament.to_text(&self.course_list.courses))
+            .context(format!("Writing {}", text_path.display()))?;
+
+        println!(
+            "Exported to {} and {}.",
+            json_path.display(),
+            text_path.display()
+        );
+        Ok(())
+    }
+
+    fn rank(&mut self) -> anyhow::Result<()> {
+        if self.course_list.ranking_progress().is_none() {
+            let player = if self.course_list.get_players().is_empty() {
+                None
+            } else {
+                let input = get_input("Rank for which player? (blank to rank the list directly): ")
+                    .context("Reading input")?;
+                let name = input.trim();
+                (!name.is_empty()).then(|| name.to_owned())
+            };
+
+            println!(
+                "This will rank every course by asking you to pick the better of two courses.\n\
+                The ranking is saved once every course has been ranked."
+            );
+            let input = get_input("Start ranking? (Y/N): ").context("Reading input")?;
+            match input.trim().to_lowercase().as_ref() {
+                "y" => {
+                    if self.course_list.start_ranking(player.as_deref()).is_err() {
+                        bail!("No player with that name");
+                    }
+                }
+                "n" => return Ok(()),
+                _ => bail!("Must select Y or N"),
+            }
+        }
+
+        if let Some(name) = self.course_list.ranking_player() {
+            println!("Ranking courses for {name}.");
+        }
+        println!(
+            "Entered ranking. Enter 1 or 2 to pick the better course.\n\
+            Type 'back' to return and resume later, or 'end' to discard the ranking."
+        );
+
+        let mut input = String::new();
+        while let Some((first, second)) = self.course_list.next_comparison() {
+            if let Some((ranked, total)) = self.course_list.ranking_progress() {
+                println!("({ranked}/{total} ranked) Which course is better?");
+            }
+            println!("1: {}", self.course_list[first]);
+            println!("2: {}", self.course_list[second]);
+
+            continue_on_err!(self.read_command(&mut input), "Error reading input");
+
+            let first_is_better = match input.trim().to_lowercase().as_ref() {
+                "1" => true,
+                "2" => false,
+
+                "back" => {
+                    println!("Returning to main list...");
+                    return Ok(());
+                }
+
+                "end" => {
+                    self.course_list.end_ranking();
+                    println!("Ranking discarded. Returning to main list...");
+                    return Ok(());
+                }
+
+                _ => {
+                    println!("Unrecognized command.");
+                    continue;
+                }
+            };
+
+            if self.course_list.answer_comparison(first_is_better).is_err() {
+                bail!("No ranking in progress");
+            }
+        }
+
+        println!("Ranking complete.");
+        Ok(())
+    }
+
+    fn players(&self) {
+        let players = self.course_list.get_players();
+        if players.is_empty() {
+            println!("There are no players.");
+            return;
+        }
+
+        for player in players {
+            let present = if player.present { "present" } else { "absent" };
+            let ranked = if player.ranking.is_empty() {
+                "not ranked"
+            } else {
+                "ranked"
+            };
+            println!(
+                "{} ({present}, {ranked}, {} banned)",
+                player.name,
+                player.banned.len()
+            );
+        }
+        println!("Pool mode: {:?}", self.course_list.get_pool_mode());
+    }
+
+    fn present(&mut self) -> anyhow::Result<()> {
+        let input = get_input("Enter the names of tonight's players, separated by commas: ")
+            .context("Reading input")?;
+
+        let mut present: Vec<usize> = Vec::new();
+        for name in input.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let player_i = self
+                .course_list
+                .find_player(name)
+                .ok_or(anyhow::anyhow!("No player named '{name}'"))?;
+            present.push(player_i);
+        }
+
+        for player_i in 0..self.course_list.get_players().len() {
+            self.course_list
+                .set_present(player_i, present.contains(&player_i));
+        }
+        println!("{} players present.", present.len());
+        Ok(())
+    }
+
+    fn ban(&mut self) -> anyhow::Result<()> {
+        let player_i = self.select_player()?;
+        let input = get_input("Search courses: ").context("Reading input")?;
+        let results: Vec<CourseId> = self
+            .course_list
+            .search_all(&input)
+            .into_iter()
+            .map(|m| m.course)
+            .collect();
+
+        let selection = self.search_sub_list(results)?;
+        self.course_list.ban(player_i, selection);
+        Ok(())
+    }
+
+    fn unban(&mut self) -> anyhow::Result<()> {
+        let player_i = self.select_player()?;
+        let banned: Vec<CourseId> = self.course_list.get_players()[player_i]
+            .banned
+            .iter()
+            .copied()
+            .collect();
+        if banned.is_empty() {
+            bail!("That player has not banned any courses");
+        }
+
+        let selection = self.search_sub_list(banned)?;
+        self.course_list.unban(player_i, selection);
+        Ok(())
+    }
+
+    fn pool(&mut self) -> anyhow::Result<()> {
+        println!(
+            "Pool modes:\n\
+            1 - Only courses no present player has banned\n\
+            2 - Present players take turns, using their own bans"
+        );
+        let input = get_input("Select a mode: ").context("Reading input")?;
+
+        let mode = match input.trim() {
+            "1" => PoolMode::Everyone,
+            "2" => PoolMode::Rotation,
+            _ => bail!("Out of bounds selection"),
+        };
+
+        self.course_list.set_pool_mode(mode);
+        println!(
+            "There are {} courses in the pool.",
+            self.course_list.get_pool().count()
+        );
+        Ok(())
+    }
+
+    fn select_player(&self) -> anyhow::Result<usize> {
+        let input = get_input("Enter the player's name: ").context("Reading input")?;
+        let name = input.trim();
+        self.course_list
+            .find_player(name)
+            .ok_or(anyhow::anyhow!("No player named '{name}'"))
+    }
+
+    fn new_player(&mut self) -> anyhow::Result<()> {
+        let input = get_input("Enter the player's name: ").context("Reading input")?;
+        let name = input.trim();
+        if name.is_empty() {
+            bail!("Name cannot be empty");
+        }
+
+        if self.course_list.add_player(name).is_err() {
+            bail!("A player named '{name}' already exists");
+        }
+        println!("Added player {name}.");
+        Ok(())
+    }
+
+    fn aggregate(&mut self) -> anyhow::Result<()> {
+        println!(
+            "Aggregation methods:\n\
+            1 - Borda count (default)\n\
+            2 - Kemeny approximation\n\
+            3 - Average rank"
+        );
+        let input = get_input("Select a method: ").context("Reading input")?;
+
+        let method = match input.trim() {
+            "" | "1" => AggregationMethod::Borda,
+            "2" => AggregationMethod::Kemeny,
+            "3" => AggregationMethod::AverageRank,
+            _ => bail!("Out of bounds selection"),
+        };
+
+        if self.course_list.aggregate_rankings(method).is_err() {
+            bail!("No player has ranked the courses yet. Use 'rank' to rank them.");
+        }
+        println!("Course ranks have been updated.");
+
+        let contested: Vec<Contested> = self
+            .course_list
+            .contested_courses()
+            .into_iter()
+            .take(CONTESTED_SHOWN)
+            .filter(|c| c.best != c.worst)
+            .collect();
+        if !contested.is_empty() {
+            println!("Most contested courses:");
+            for c in contested {
+                println!(
+                    "{} (ranked {}-{}, std. dev. {:.1})",
+                    self.course_list[c.course], c.best, c.worst, c.std_dev
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn help() {
+        println!("---------------------------------------------------");
+
+        println!(
+            "Blank input: Generate and remove a random course from the pool.\n\
+            q, quit:      Exit.\n\
+            help:         Show this help text.\n\
+            save:         Save the list.\n\
+            saveas:       Save the list under a new name and keep using it from there.\n\
+            fork:         Copy the list and its history to a new save, and keep using this one.\n\
+            saves:        Manage saves: list, rename, copy, delete or create them.\n\
+            A '*' before the prompt means there are unsaved changes."
+        );
+
+        println!(
+            "Information:\n\
+            remaining, re, ls: List remaining courses.\n\
+            used:              List used courses.\n\
+            history:           Show history.\n\
+            log:               List every action in history, oldest first.\n\
+            export:            Write a Markdown or HTML report of the history and results.\n"
+        );
+
+        println!(
+            "List editing:\n\
+            reset:           Reset the course list and delete all history.\n\
+            back:            Roll back in history.\n\
+            forward:         Roll forward in history.\n\
+            add:             Add a previously removed course.\n\
+            remove, rm, pop: Remove a currently active course.\n\
+            create:          Add a new course to the game, e.g. from a new wave.\n\
+            edit:            Change a course's name, aliases, origin, coordinate, rank or tags.\n\
+            delete:          Delete a course from the game.\n\
+            Searches match names, aliases like 'DKJ', origins and coordinates like '3-1-4', and\n\
+            add and remove pick a course right away when it is the only close match.\n"
+        );
+
+        println!(
+            "Special:\n\
+            tier:       Generate a tiered sub-list, or resume the one in progress.\n\
+            veto:       Draw candidates for players to strike, then race the rest.\n\
+            tournament: Run a tournament, or resume the one in progress.\n\
+            rank:       Rank courses by comparing pairs, or resume the ranking in progress.\n\
+            aggregate:  Combine the players' rankings into the course ranks.\n"
+        );
+
+        println!(
+            "Players:\n\
+            players:   List players.\n\
+            newplayer: Add a player.\n\
+            present:   Set who is playing tonight.\n\
+            ban:       Ban a course for a player.\n\
+            unban:     Lift a player's ban on a course.\n\
+            pool:      Choose how bans limit the courses that can be rolled.\n"
+        );
+
+        println!(
+            "Results:\n\
+            result:        Record placements for a removed course, the last by default.\n\
+            standings:     Show prix and season standings.\n\
+            leaders:       Show the best player on each course.\n\
+            points:        Set the points awarded for each placement.\n\
+            results:       List recorded results.\n\
+            editresult:    Change the placements of a recorded result.\n\
+            delresult:     Delete a recorded result.\n\
+            ratings:       Show player ratings and their history.\n\
+            courseratings: Show player ratings on a course."
+        );
+
+        println!("---------------------------------------------------");
+    }
+}
+
+// Undoing and redoing are otherwise silent, so report which courses they brought back or removed
+fn report_undo(change: &Change) {
+    if !matches!(change.event, Event::Undo | Event::Redo) {
+        return;
+    }
+
+    // Undoing a creation or redoing a deletion takes the course out of the list altogether
+    for course in change.added() {
+        if let Some(course) = change.after.get_course(course) {
+            println!("Added back: {course}");
+        }
+    }
+    for course in change.removed() {
+        if let Some(course) = change.after.get_course(course) {
+            println!("Removed: {course}");
+        }
+    }
+}
+
+// Split a comma separated list, dropping blank items
+fn parse_list(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+fn pick_save(saves: &[SaveInfo]) -> anyhow::Result<&SaveInfo> {
+    for (i, save) in saves.iter().enumerate() {
+        println!("{}: {save}", i + 1);
+    }
+    let input = get_input(":> ").context("Reading input")?;
+
+    let index: usize = input
+        .trim()
+        .parse()
+        .context(format!("Parsing input '{input}' into number"))?;
+
+    saves
+        .get(index.wrapping_sub(1))
+        .ok_or(anyhow::anyhow!("Out of bounds selection"))
+}
+
+fn confirm_overwrite(e: &io::Error) -> anyhow::Result<bool> {
+    println!("{e}. Overwrite it? (capital 'Y' to confirm): ");
+    let input = get_input(":> ").context("Reading input")?;
+    Ok(input.trim() == "Y")
 }
@@ -0,0 +1,413 @@
+//! Module for writing reports of a `CourseList`'s history, sessions and results, e.g. to post a
+//! recap after a game night. Reports are written as Markdown or HTML.
+//!
+//! A report lists every course raced in order, a breakdown by origin, the courses never rolled,
+//! the sessions the list was used in, and the standings if any results were recorded. Courses
+//! raced before the list was last reset are included. See `CourseList::get_removed_in_order`.
+//! Times are given in UTC.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::courses::course::{Course, CourseId, Origin};
+use crate::courses::course_list::CourseList;
+use crate::courses::session::Session;
+use crate::saves::plural;
+
+/// File format of a report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Markdown, e.g. to paste into a chat.
+    Markdown,
+    /// A standalone HTML page.
+    Html,
+}
+
+impl ReportFormat {
+    /// Pick the format matching a file's extension: `.md` or `.html`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_ref() {
+            "md" | "markdown" => Some(ReportFormat::Markdown),
+            "html" | "htm" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+// A piece of a report, rendered the same way in every format
+enum Block {
+    Heading(String),
+    Paragraph(String),
+    List {
+        ordered: bool,
+        items: Vec<String>,
+    },
+    Table {
+        header: Vec<&'static str>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+/// Write a report of the list in the given format.
+pub fn write_report(list: &CourseList, format: ReportFormat) -> String {
+    let title = format!(
+        "Report for {}",
+        list.save_name.file_stem().unwrap_or_default().display()
+    );
+    let blocks = report_blocks(list);
+
+    match format {
+        ReportFormat::Markdown => markdown(&title, &blocks),
+        ReportFormat::Html => html(&title, &blocks),
+    }
+}
+
+fn report_blocks(list: &CourseList) -> Vec<Block> {
+    let courses = &list.courses;
+    let raced = list.get_removed_in_order();
+    let rolled: BTreeSet<CourseId> = raced.iter().copied().collect();
+    let mut never: Vec<&Course> = courses.iter().filter(|c| !rolled.contains(&c.id)).collect();
+    never.sort();
+    let sessions = list.get_sessions();
+    let total: Duration = sessions.iter().map(Session::duration).sum();
+
+    let mut blocks = vec![Block::Paragraph(format!(
+        "{} raced over {}, lasting {} in total. {} of {} courses were never rolled.",
+        plural(raced.len(), "course"),
+        plural(sessions.len(), "session"),
+        format_duration(total),
+        never.len(),
+        courses.len(),
+    ))];
+
+    blocks.push(Block::Heading("Sessions".to_owned()));
+    if sessions.is_empty() {
+        blocks.push(Block::Paragraph("No sessions were recorded.".to_owned()));
+    } else {
+        blocks.push(Block::Table {
+            header: vec!["#", "Date", "Started", "Duration", "Courses rolled"],
+            rows: sessions
+                .iter()
+                .enumerate()
+                .map(|(i, s)| {
+                    vec![
+                        (i + 1).to_string(),
+                        format_date(s.started),
+                        format_clock(s.started),
+                        format_duration(s.duration()),
+                        s.rolled.to_string(),
+                    ]
+                })
+                .collect(),
+        });
+    }
+
+    blocks.push(Block::Heading("Courses raced".to_owned()));
+    if raced.is_empty() {
+        blocks.push(Block::Paragraph("No courses were raced.".to_owned()));
+    } else {
+        blocks.push(Block::List {
+            ordered: true,
+            items: raced.iter().map(|&id| describe(&list[id])).collect(),
+        });
+    }
+
+    blocks.push(Block::Heading("By origin".to_owned()));
+    blocks.push(origin_table(list, &raced, &never));
+
+    blocks.push(Block::Heading("Never rolled".to_owned()));
+    if never.is_empty() {
+        blocks.push(Block::Paragraph("Every course was rolled.".to_owned()));
+    } else {
+        blocks.push(Block::List {
+            ordered: false,
+            items: never.iter().map(|course| describe(course)).collect(),
+        });
+    }
+
+    let standings = list.season_standings();
+    if !standings.is_empty() {
+        blocks.push(Block::Heading("Standings".to_owned()));
+        blocks.push(Block::Table {
+            header: vec!["Player", "Points", "Races", "Wins"],
+            rows: standings
+                .into_iter()
+                .map(|s| {
+                    vec![
+                        s.player,
+                        s.points.to_string(),
+                        s.races.to_string(),
+                        s.wins.to_string(),
+                    ]
+                })
+                .collect(),
+        });
+    }
+
+    blocks
+}
+
+// Courses in the game, times raced and courses never rolled, by origin
+fn origin_table(list: &CourseList, raced: &[CourseId], never: &[&Course]) -> Block {
+    let mut origins: BTreeMap<Origin, (usize, usize, usize)> = BTreeMap::new();
+    for course in &list.courses {
+        origins.entry(course.origin).or_default().0 += 1;
+    }
+    for &id in raced {
+        origins.entry(list[id].origin).or_default().1 += 1;
+    }
+    for course in never {
+        origins.entry(course.origin).or_default().2 += 1;
+    }
+
+    Block::Table {
+        header: vec!["Origin", "Courses", "Times raced", "Never rolled"],
+        rows: origins
+            .into_iter()
+            .map(|(origin, (count, raced, never))| {
+                vec![
+                    origin.to_string(),
+                    count.to_string(),
+                    raced.to_string(),
+                    never.to_string(),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn describe(course: &Course) -> String {
+    format!("{} ({} {})", course.name, course.origin, course.coord)
+}
+
+fn markdown(title: &str, blocks: &[Block]) -> String {
+    let mut out = format!("# {}\n", escape_markdown(title));
+
+    for block in blocks {
+        out.push('\n');
+        match block {
+            Block::Heading(text) => {
+                let _ = writeln!(out, "## {}", escape_markdown(text));
+            }
+            Block::Paragraph(text) => {
+                let _ = writeln!(out, "{}", escape_markdown(text));
+            }
+            Block::List { ordered, items } => {
+                for (i, item) in items.iter().enumerate() {
+                    let marker = if *ordered {
+                        format!("{}.", i + 1)
+                    } else {
+                        "-".to_owned()
+                    };
+                    let _ = writeln!(out, "{marker} {}", escape_markdown(item));
+                }
+            }
+            Block::Table { header, rows } => {
+                let _ = writeln!(out, "| {} |", header.join(" | "));
+                let _ = writeln!(out, "|{}", " --- |".repeat(header.len()));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|c| escape_markdown(c)).collect();
+                    let _ = writeln!(out, "| {} |", cells.join(" | "));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+fn html(title: &str, blocks: &[Block]) -> String {
+    let title = escape_html(title);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+        <style>\nbody {{ font-family: sans-serif; }}\n\
+        table {{ border-collapse: collapse; }}\n\
+        th, td {{ border: 1px solid #999; padding: 0.2em 0.6em; }}\n</style>\n\
+        </head>\n<body>\n<h1>{title}</h1>\n"
+    );
+
+    for block in blocks {
+        match block {
+            Block::Heading(text) => {
+                let _ = writeln!(out, "<h2>{}</h2>", escape_html(text));
+            }
+            Block::Paragraph(text) => {
+                let _ = writeln!(out, "<p>{}</p>", escape_html(text));
+            }
+            Block::List { ordered, items } => {
+                let tag = if *ordered { "ol" } else { "ul" };
+                let _ = writeln!(out, "<{tag}>");
+                for item in items {
+                    let _ = writeln!(out, "<li>{}</li>", escape_html(item));
+                }
+                let _ = writeln!(out, "</{tag}>");
+            }
+            Block::Table { header, rows } => {
+                out.push_str("<table>\n<tr>");
+                for cell in header {
+                    let _ = write!(out, "<th>{}</th>", escape_html(cell));
+                }
+                out.push_str("</tr>\n");
+                for row in rows {
+                    out.push_str("<tr>");
+                    for cell in row {
+                        let _ = write!(out, "<td>{}</td>", escape_html(cell));
+                    }
+                    out.push_str("</tr>\n");
+                }
+                out.push_str("</table>\n");
+            }
+        }
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '|' | '[' | ']' | '<' | '>' | '#') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match minutes / 60 {
+        0 => format!("{minutes}m"),
+        hours => format!("{hours}h {:02}m", minutes % 60),
+    }
+}
+
+// Format a time in seconds since the Unix epoch as a UTC date, e.g. "2025-06-01"
+fn format_date(secs: u64) -> String {
+    // Days to civil date, from Howard Hinnant's date algorithms
+    let z = secs / 86_400 + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!("{year}-{month:02}-{day:02}")
+}
+
+// Format a time in seconds since the Unix epoch as a UTC time of day, e.g. "20:15 UTC"
+fn format_clock(secs: u64) -> String {
+    let minutes = secs % 86_400 / 60;
+    format!("{:02}:{:02} UTC", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::courses::course::Coord;
+
+    fn raced_list() -> CourseList {
+        let courses = vec![
+            Course::new(
+                CourseId(0),
+                1,
+                Coord::new(1, 1, 1),
+                Origin::MK8,
+                "Mario *Kart*",
+            ),
+            Course::new(
+                CourseId(1),
+                2,
+                Coord::new(1, 1, 2),
+                Origin::MK8,
+                "Water Park",
+            ),
+            Course::new(
+                CourseId(2),
+                3,
+                Coord::new(1, 1, 3),
+                Origin::N64,
+                "Yoshi <Valley>",
+            ),
+        ];
+        let mut list = CourseList::from_courses("game night.json", courses);
+        list.remove(CourseId(2));
+        list.reset();
+        list.remove(CourseId(0));
+        list
+    }
+
+    #[test]
+    fn markdown_reports_list_courses_in_race_order() {
+        let report = write_report(&raced_list(), ReportFormat::Markdown);
+
+        assert!(report.starts_with("# Report for game night\n"));
+        assert!(report.contains(
+            "2 courses raced over 1 session, lasting 0m in total. 1 of 3 courses were never \
+            rolled."
+        ));
+        assert!(report.contains(
+            "## Courses raced\n\n1. Yoshi \\<Valley\\> (N64 1-1-3)\n2. Mario \\*Kart\\* (MK8 1-1-1)\n"
+        ));
+        assert!(report.contains("## Never rolled\n\n- Water Park (MK8 1-1-2)\n"));
+        assert!(report.contains("| N64 | 1 | 1 | 0 |\n"));
+        assert!(report.contains("| MK8 | 2 | 1 | 1 |\n"));
+        assert!(!report.contains("## Standings"));
+    }
+
+    #[test]
+    fn html_reports_escape_names() {
+        let mut list = raced_list();
+        list.add_player("Alice & Bob").unwrap();
+        let placements = [("Alice & Bob".to_owned(), 1)].into();
+        list.record_result(CourseId(0), placements).unwrap();
+
+        let report = write_report(&list, ReportFormat::Html);
+        assert!(report.contains("<h1>Report for game night</h1>"));
+        assert!(report.contains("<li>Yoshi &lt;Valley&gt; (N64 1-1-3)</li>"));
+        assert!(report.contains("<h2>Standings</h2>"));
+        assert!(
+            report.contains("<tr><td>Alice &amp; Bob</td><td>15</td><td>1</td><td>1</td></tr>")
+        );
+        assert!(report.ends_with("</body>\n</html>\n"));
+    }
+
+    #[test]
+    fn formats_from_extensions() {
+        let format = |path: &str| ReportFormat::from_path(Path::new(path));
+        assert_eq!(format("recap.MD"), Some(ReportFormat::Markdown));
+        assert_eq!(format("recap.htm"), Some(ReportFormat::Html));
+        assert_eq!(format("recap.txt"), None);
+        assert_eq!(format("recap"), None);
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(format_duration(Duration::from_secs(59)), "0m");
+        assert_eq!(format_duration(Duration::from_mins(185)), "3h 05m");
+        assert_eq!(format_date(0), "1970-01-01");
+        // 2024-02-29 20:15:00 UTC
+        assert_eq!(format_date(1_709_237_700), "2024-02-29");
+        assert_eq!(format_clock(1_709_237_700), "20:15 UTC");
+    }
+}
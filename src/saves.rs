@@ -0,0 +1,297 @@
+//! Module for managing the saves in `SAVES_DIR`: listing them with some details, and renaming,
+//! copying, deleting and creating them.
+//!
+//! Saves are `.json` files directly inside `SAVES_DIR`, named by their file name, e.g.
+//! `my_save.json`. Anything else in the directory, such as lockfiles, is ignored. Saves in use by
+//! a session cannot be renamed or deleted. See `lock::SaveLock`.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use serde_json::Value;
+
+use crate::courses::course_list::CourseList;
+use crate::lock::SaveLock;
+use crate::{SAVES_DIR, write_atomic};
+
+/// Extension of save files.
+pub const SAVE_EXTENSION: &str = "json";
+
+/// Details read from a save file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveDetails {
+    /// Number of courses in the game.
+    pub courses: usize,
+    /// Number of active courses.
+    pub active: usize,
+    /// Number of actions in history that can be undone.
+    pub actions: usize,
+    /// Number of players.
+    pub players: usize,
+    /// Number of recorded race results.
+    pub results: usize,
+}
+
+/// A save found in `SAVES_DIR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SaveInfo {
+    /// File name of the save, as given to `CourseList::restore_save`.
+    pub name: PathBuf,
+    /// When the save was last written, if known.
+    pub modified: Option<SystemTime>,
+    /// Whether a session is using the save.
+    pub in_use: bool,
+    /// Details of the save, or `None` if it could not be read as a `CourseList`.
+    pub details: Option<SaveDetails>,
+}
+
+impl SaveInfo {
+    fn read(name: PathBuf) -> Self {
+        let path = SAVES_DIR.join(&name);
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let in_use = SaveLock::is_locked(&path).unwrap_or(false);
+        let details = fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| CourseList::from_save_data(&data).ok())
+            .map(|list| SaveDetails {
+                courses: list.courses.len(),
+                active: list.get_current().count(),
+                actions: list.get_history().past.len(),
+                players: list.get_players().len(),
+                results: list.get_results().len(),
+            });
+
+        SaveInfo {
+            name,
+            modified,
+            in_use,
+            details,
+        }
+    }
+}
+
+impl fmt::Display for SaveInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.name.display())?;
+
+        match &self.details {
+            Some(d) => write!(
+                f,
+                "{}/{} courses active, {}, {}, {}",
+                d.active,
+                d.courses,
+                plural(d.actions, "action"),
+                plural(d.players, "player"),
+                plural(d.results, "result"),
+            )?,
+            None => write!(f, "unreadable")?,
+        }
+
+        if let Some(modified) = self.modified {
+            write!(f, ", saved {}", format_age(modified))?;
+        }
+        if self.in_use {
+            write!(f, " (in use)")?;
+        }
+        Ok(())
+    }
+}
+
+/// List every save in `SAVES_DIR`, sorted by name.
+///
+/// # Errors
+/// - If `SAVES_DIR` cannot be read.
+pub fn list_saves() -> io::Result<Vec<SaveInfo>> {
+    let mut names: Vec<PathBuf> = Vec::new();
+    for entry in fs::read_dir(&*SAVES_DIR)? {
+        let entry = entry?;
+        let name = PathBuf::from(entry.file_name());
+        if entry.file_type()?.is_file() && is_save_name(&name) {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    Ok(names.into_iter().map(SaveInfo::read).collect())
+}
+
+/// Rename a save. `to` is sanitized with `sanitize_save_name`, and the save name stored inside
+/// the save is updated to match. Returns the sanitized name.
+///
+/// # Errors
+/// - If `from` or `to` is not a valid save name. See `sanitize_save_name`.
+/// - If either save is in use. The error is of kind `ErrorKind::ResourceBusy`.
+/// - If `to` already exists. The error is of kind `ErrorKind::AlreadyExists`.
+/// - If `from` cannot be read as JSON, or `to` cannot be written.
+pub fn rename_save(from: impl AsRef<Path>, to: &str) -> io::Result<PathBuf> {
+    let from_path = save_path(from.as_ref())?;
+    let _from_lock = SaveLock::acquire(&from_path)?;
+
+    let to = copy_locked(&from_path, to)?;
+    fs::remove_file(&from_path)?;
+    Ok(to)
+}
+
+/// Copy a save to a new name. `to` is sanitized with `sanitize_save_name`, and the save name
+/// stored inside the copy is updated to match. Returns the sanitized name.
+///
+/// # Errors
+/// - If `from` or `to` is not a valid save name. See `sanitize_save_name`.
+/// - If `to` is in use. The error is of kind `ErrorKind::ResourceBusy`.
+/// - If `to` already exists. The error is of kind `ErrorKind::AlreadyExists`.
+/// - If `from` cannot be read as JSON, or `to` cannot be written.
+pub fn copy_save(from: impl AsRef<Path>, to: &str) -> io::Result<PathBuf> {
+    copy_locked(&save_path(from.as_ref())?, to)
+}
+
+// Copy the save at `from_path` to the save named `to`, locking `to` throughout
+fn copy_locked(from_path: &Path, to: &str) -> io::Result<PathBuf> {
+    let to = sanitize_save_name(to)?;
+    let to_path = SAVES_DIR.join(&to);
+    let _to_lock = SaveLock::acquire(&to_path)?;
+    if to_path.try_exists()? {
+        return Err(already_exists(&to));
+    }
+
+    // Edited as plain JSON, so that nothing in the save is lost in translation
+    let mut save: Value = serde_json::from_str(&fs::read_to_string(from_path)?)?;
+    set_save_name(&mut save, &to);
+    write_atomic(&to_path, serde_json::to_string_pretty(&save)?.as_bytes())?;
+    Ok(to)
+}
+
+pub(crate) fn set_save_name(save: &mut Value, save_name: &Path) {
+    if let Some(fields) = save.as_object_mut() {
+        fields.insert(
+            "save_name".to_owned(),
+            Value::from(save_name.to_string_lossy()),
+        );
+    }
+}
+
+/// Move a save to the trash.
+///
+/// # Errors
+/// - If `save_name` is not a valid save name. See `sanitize_save_name`.
+/// - If the save is in use. The error is of kind `ErrorKind::ResourceBusy`.
+/// - If the save does not exist, or cannot be moved to the trash.
+pub fn delete_save(save_name: impl AsRef<Path>) -> io::Result<()> {
+    let path = save_path(save_name.as_ref())?;
+    let _lock = SaveLock::acquire(&path)?;
+    if !path.try_exists()? {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} does not exist", path.display()),
+        ));
+    }
+
+    trash::delete(&path).map_err(io::Error::other)
+}
+
+/// Create a new save with the game and courses of `template`, every course active, no history
+/// and no players. See `CourseList::blank_copy`. `save_name` is sanitized with
+/// `sanitize_save_name`. Returns the sanitized name.
+///
+/// # Errors
+/// - If `save_name` is not a valid save name. See `sanitize_save_name`.
+/// - If the save is in use. The error is of kind `ErrorKind::ResourceBusy`.
+/// - If the save already exists. The error is of kind `ErrorKind::AlreadyExists`.
+/// - If the save cannot be written. See `CourseList::dump_list`.
+pub fn create_save(save_name: &str, template: &CourseList) -> io::Result<PathBuf> {
+    let save_name = sanitize_save_name(save_name)?;
+    let list = template.blank_copy(&save_name);
+    // Locked before checking, so no other session can create the save in between
+    list.ensure_locked()?;
+    if list.save_path().try_exists()? {
+        return Err(already_exists(&save_name));
+    }
+
+    list.dump_list()?;
+    Ok(save_name)
+}
+
+/// Turn user input into a save name. Surrounding whitespace and leading dots are removed,
+/// characters that are not allowed in file names are replaced with '_', and the `.json` extension
+/// is added if it is missing. For example, `Game night: 2/3` becomes `Game night_ 2_3.json`.
+///
+/// # Errors
+/// - If nothing is left of the name. The error is of kind `ErrorKind::InvalidInput`.
+pub fn sanitize_save_name(name: &str) -> io::Result<PathBuf> {
+    let name = name.trim();
+    let extension = format!(".{SAVE_EXTENSION}");
+    let stem = name
+        .len()
+        .checked_sub(extension.len())
+        .and_then(|i| Some((name.get(..i)?, name.get(i..)?)))
+        .filter(|(_, ext)| ext.eq_ignore_ascii_case(&extension))
+        .map_or(name, |(stem, _)| stem);
+
+    let stem: String = stem
+        .chars()
+        .map(|c| {
+            let reserved = matches!(c, '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*');
+            if reserved || c.is_control() { '_' } else { c }
+        })
+        .collect();
+    let stem = stem.trim().trim_start_matches('.');
+
+    if stem.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{name}' is not a valid save name"),
+        ));
+    }
+    Ok(PathBuf::from(format!("{stem}{extension}")))
+}
+
+// Path of an existing save, rejecting names that `sanitize_save_name` would change, so that
+// nothing outside `SAVES_DIR` or other than a save can be touched
+fn save_path(save_name: &Path) -> io::Result<PathBuf> {
+    let valid = save_name
+        .to_str()
+        .is_some_and(|name| sanitize_save_name(name).is_ok_and(|sanitized| sanitized == save_name));
+    if !valid {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("'{}' is not a valid save name", save_name.display()),
+        ));
+    }
+    Ok(SAVES_DIR.join(save_name))
+}
+
+/// Check whether a file name is that of a save.
+pub fn is_save_name(name: &Path) -> bool {
+    name.extension().is_some_and(|ext| ext == SAVE_EXTENSION)
+}
+
+pub(crate) fn already_exists(name: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", name.display()),
+    )
+}
+
+pub(crate) fn plural(n: usize, unit: &str) -> String {
+    if n == 1 {
+        format!("{n} {unit}")
+    } else {
+        format!("{n} {unit}s")
+    }
+}
+
+fn format_age(time: SystemTime) -> String {
+    let secs = time.elapsed().map_or(0, |age| age.as_secs());
+    let (n, unit) = match secs {
+        0..60 => return "just now".to_owned(),
+        60..3600 => (secs / 60, "minute"),
+        3600..86_400 => (secs / 3600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    format!(
+        "{} ago",
+        plural(usize::try_from(n).unwrap_or(usize::MAX), unit)
+    )
+}
@@ -0,0 +1,275 @@
+use std::time::Instant;
+
+use anyhow::{self, Context};
+use my_lib::continue_on_err;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Request, Response};
+
+use mk8d_random::autosave::{Autosave, AutosavePolicy};
+use mk8d_random::courses::course::CourseId;
+use mk8d_random::courses::course_list::CourseList;
+use mk8d_random::courses::prix::PrixEntry;
+use mk8d_random::courses::tiers::TierStrategy;
+use mk8d_random::overlay::OverlayWriter;
+
+use crate::broadcaster::Broadcaster;
+
+// An error response: HTTP status code and message
+type ApiError = (u16, String);
+
+#[derive(Deserialize)]
+struct CourseRequest {
+    course: CourseId,
+}
+
+#[derive(Deserialize)]
+struct PrixRequest {
+    strategy: TierStrategy,
+}
+
+pub struct Server {
+    course_list: CourseList,
+}
+
+impl Server {
+    pub fn new(course_list: CourseList) -> Self {
+        Self { course_list }
+    }
+
+    pub fn with_overlay(mut self, overlay: OverlayWriter) -> anyhow::Result<Self> {
+        overlay
+            .write(&self.course_list)
+            .context("Writing overlay files")?;
+        self.course_list.subscribe(overlay);
+        Ok(self)
+    }
+
+    pub fn with_broadcaster(mut self, port: u16) -> anyhow::Result<Self> {
+        let broadcaster = Broadcaster::bind(port, state_json(&self.course_list))?;
+        self.course_list.subscribe(broadcaster);
+        Ok(self)
+    }
+
+    pub fn with_autosave(mut self, policy: AutosavePolicy) -> Self {
+        self.course_list.subscribe(Autosave::new(policy));
+        self
+    }
+
+    pub fn run(&mut self, port: u16) -> anyhow::Result<()> {
+        let server = tiny_http::Server::http(("127.0.0.1", port))
+            .map_err(|e| anyhow::anyhow!(e))
+            .context(format!("Binding to port {port}"))?;
+        println!("Serving on http://127.0.0.1:{port}. Press Ctrl+C to stop.");
+
+        loop {
+            // Catch up on held back work, e.g. autosaving, whenever it is due between requests
+            let request = match self.course_list.next_flush() {
+                Some(at) => server.recv_timeout(at.saturating_duration_since(Instant::now())),
+                None => server.recv().map(Some),
+            };
+            let Some(mut request) = continue_on_err!(request, "Error receiving request") else {
+                self.course_list.flush_observers(false);
+                self.report_observer_errors();
+                continue;
+            };
+
+            let response = match self.handle(&mut request) {
+                Ok(body) => json_response(200, &body),
+                Err((status, message)) => json_response(status, &json!({ "error": message })),
+            };
+            self.report_observer_errors();
+            continue_on_err!(request.respond(response), "Error responding to request");
+        }
+    }
+
+    fn report_observer_errors(&mut self) {
+        for e in self.course_list.take_observer_errors() {
+            eprintln!("Error: {e}");
+        }
+    }
+
+    fn handle(&mut self, request: &mut Request) -> Result<Value, ApiError> {
+        let method = request.method().clone();
+        let path = request
+            .url()
+            .split('?')
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+
+        match (method, path.as_str()) {
+            (Method::Get, "/list") => Ok(list_json(&self.course_list)),
+            (Method::Get, "/courses") => Ok(courses_json(&self.course_list)),
+            (Method::Get, "/history") => Ok(history_json(&self.course_list)),
+            (Method::Get, "/prix") => Ok(prix_json(&self.course_list)),
+            (Method::Get, "/state") => Ok(state_json(&self.course_list)),
+
+            (Method::Post, "/roll") => self.roll(),
+            (Method::Post, "/add") => {
+                let CourseRequest { course } = parse_body(request)?;
+                self.check_course(course)?;
+                if !self.course_list.get_removed().any(|id| id == course) {
+                    return Err((409, format!("Course {course} is not removed")));
+                }
+                self.course_list.add(course);
+                Ok(list_json(&self.course_list))
+            }
+            (Method::Post, "/remove") => {
+                let CourseRequest { course } = parse_body(request)?;
+                self.check_course(course)?;
+                if !self.course_list.get_current().any(|id| id == course) {
+                    return Err((409, format!("Course {course} is not active")));
+                }
+                self.course_list.remove(course);
+                Ok(list_json(&self.course_list))
+            }
+            (Method::Post, "/undo") => {
+                self.course_list
+                    .roll_back()
+                    .map_err(|()| (409, "No history found".to_owned()))?;
+                Ok(history_json(&self.course_list))
+            }
+            (Method::Post, "/redo") => {
+                self.course_list
+                    .roll_forward()
+                    .map_err(|()| (409, "No future found".to_owned()))?;
+                Ok(history_json(&self.course_list))
+            }
+            (Method::Post, "/reset") => {
+                self.course_list.reset();
+                Ok(list_json(&self.course_list))
+            }
+            (Method::Post, "/prix") => {
+                let PrixRequest { strategy } = parse_body(request)?;
+                self.course_list
+                    .start_prix(&strategy)
+                    .map_err(|()| (400, "Could not divide courses into tiers".to_owned()))?;
+                Ok(prix_json(&self.course_list))
+            }
+            (Method::Post, "/prix/roll") => {
+                let entry = self
+                    .course_list
+                    .roll_prix()
+                    .ok_or((409, "No tiered list in progress".to_owned()))?;
+                Ok(json!({
+                    "label": entry.label,
+                    "course": course_json(&self.course_list, entry.course),
+                }))
+            }
+            (Method::Post, "/save") => {
+                let modified = self
+                    .course_list
+                    .modified_externally()
+                    .map_err(|e| (500, format!("Checking the save file: {e}")))?;
+                if modified {
+                    return Err((
+                        409,
+                        "The save file was modified since it was loaded".to_owned(),
+                    ));
+                }
+
+                self.course_list
+                    .dump_list()
+                    .map_err(|e| (500, format!("Saving list: {e}")))?;
+                Ok(json!({ "saved": self.course_list.save_name }))
+            }
+
+            _ => Err((404, format!("No route for {path}"))),
+        }
+    }
+
+    fn roll(&mut self) -> Result<Value, ApiError> {
+        let course = self
+            .course_list
+            .roll()
+            .ok_or((409, "No courses left in the pool".to_owned()))?;
+        Ok(course_json(&self.course_list, course))
+    }
+
+    fn check_course(&self, course: CourseId) -> Result<(), ApiError> {
+        if self.course_list.get_course(course).is_some() {
+            Ok(())
+        } else {
+            Err((400, format!("No course with id {course}")))
+        }
+    }
+}
+
+fn parse_body<T: DeserializeOwned>(request: &mut Request) -> Result<T, ApiError> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|e| (400, format!("Reading body: {e}")))?;
+    serde_json::from_str(&body).map_err(|e| (400, format!("Parsing body: {e}")))
+}
+
+fn json_response(status: u16, body: &Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    // The header is static and valid, so unwrap() is fine here
+    let content_type = Header::from_bytes("Content-Type", "application/json").unwrap();
+    Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn list_json(course_list: &CourseList) -> Value {
+    let current: Vec<Value> = course_list
+        .get_current()
+        .map(|id| course_json(course_list, id))
+        .collect();
+    let removed: Vec<Value> = course_list
+        .get_removed()
+        .map(|id| course_json(course_list, id))
+        .collect();
+    json!({ "current": current, "removed": removed })
+}
+
+fn courses_json(course_list: &CourseList) -> Value {
+    let courses: Vec<Value> = course_list
+        .courses
+        .iter()
+        .map(|c| course_json(course_list, c.id))
+        .collect();
+    Value::Array(courses)
+}
+
+fn history_json(course_list: &CourseList) -> Value {
+    let history = course_list.get_history();
+    json!({
+        "past": history.log(&course_list.courses, course_list.get_players()),
+        "can_undo": !history.past.is_empty(),
+        "can_redo": !history.future.is_empty(),
+    })
+}
+
+fn prix_json(course_list: &CourseList) -> Value {
+    let Some(prix) = course_list.get_prix() else {
+        return Value::Null;
+    };
+
+    let entries = |entries: &[PrixEntry]| -> Vec<Value> {
+        entries
+            .iter()
+            .map(|e| json!({ "label": e.label, "course": course_json(course_list, e.course) }))
+            .collect()
+    };
+    json!({
+        "number": prix.number,
+        "remaining": entries(&prix.remaining),
+        "raced": entries(&prix.raced),
+    })
+}
+
+pub fn state_json(course_list: &CourseList) -> Value {
+    json!({
+        "list": list_json(course_list),
+        "history": history_json(course_list),
+        "prix": prix_json(course_list),
+    })
+}
+
+fn course_json(course_list: &CourseList, course: CourseId) -> Value {
+    json!({ "id": course, "course": course_list[course] })
+}
@@ -1,7 +1,7 @@
-use std::fs::DirEntry;
 use std::io;
 
 use mk8d_random::courses::course_list::CourseList;
+use mk8d_random::saves::SaveInfo;
 use ratatui::DefaultTerminal;
 
 #[derive(Debug)]
@@ -16,7 +16,7 @@ pub enum Screen {
 
 #[derive(Debug)]
 pub enum Popup {
-    Quit, // Quit confirmation
+    Quit, // Save, discard or cancel when quitting with unsaved changes
 }
 
 #[derive(Debug)]
@@ -27,7 +27,7 @@ pub struct Tui {
 }
 
 impl Tui {
-    pub fn new(saves: Vec<DirEntry>) -> anyhow::Result<Self> {
+    pub fn new(saves: Vec<SaveInfo>) -> anyhow::Result<Self> {
         if saves.is_empty() {
             return Self::pick_default();
         }
@@ -39,7 +39,7 @@ impl Tui {
         todo!();
     }
 
-    fn load_save(saves: Vec<DirEntry>) -> anyhow::Result<Self> {
+    fn load_save(saves: Vec<SaveInfo>) -> anyhow::Result<Self> {
         todo!();
     }
 